@@ -0,0 +1,3 @@
+import { name } from "./name.js";
+
+export const greeting = `hello ${name}`;
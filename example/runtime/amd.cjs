@@ -0,0 +1,28 @@
+// Minimal AMD host: runs the defined module from stdin against a fixed set
+// of vendor modules.
+const code = require("fs").readFileSync(0, "utf8");
+
+const vendor = {
+    "vendor/lib": { wibble: function () { return "bibble"; } },
+};
+
+// The local require that `import()` of a dependency turns into.
+function amdRequire(deps, callback) {
+    callback.apply(null, deps.map(function (dep) { return vendor[dep]; }));
+}
+
+globalThis.define = function (id, deps, factory) {
+    if (typeof id !== "string") {
+        factory = deps;
+        deps = id;
+    }
+
+    const exports = {};
+    factory.apply(null, deps.map(function (dep) {
+        if (dep === "exports") return exports;
+        if (dep === "require") return amdRequire;
+        return vendor[dep];
+    }));
+};
+
+eval(code);
@@ -0,0 +1,9 @@
+// Runs the code of a `--envelope` bundle read from stdin, after checking
+// its map came along with it.
+const envelope = JSON.parse(require("fs").readFileSync(0, "utf8"));
+
+if (envelope.map.version !== 3 || !envelope.map.mappings) {
+    throw new Error("envelope has no source map");
+}
+
+import("data:text/javascript," + encodeURIComponent(envelope.code));
@@ -0,0 +1,3 @@
+// A transform that gives different output every time it's run, for
+// --audit-determinism to catch.
+console.log(`export default "${Math.random()}";`);
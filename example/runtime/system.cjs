@@ -0,0 +1,11 @@
+// Minimal System.register host: runs the registered module from stdin.
+const code = require("fs").readFileSync(0, "utf8");
+
+globalThis.System = {
+    register(deps, declare) {
+        const module = declare(function () {}, {});
+        module.execute();
+    },
+};
+
+eval(code);
@@ -0,0 +1,5 @@
+import { b } from "./b.js";
+
+export const a = "a";
+
+console.log(a + b);
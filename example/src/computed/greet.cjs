@@ -0,0 +1,3 @@
+module.exports = function greet(lang) {
+    return require(`./locales/${lang}.cjs`);
+};
@@ -0,0 +1,6 @@
+import greet from "./greet.cjs";
+
+const lang = "fr";
+const { hello } = await import(`./locales/${lang}.js`);
+
+console.log(hello, greet("de"));
@@ -0,0 +1,4 @@
+import { build } from "conditional";
+import { feature } from "conditional/feature";
+
+console.log(build, feature);
@@ -0,0 +1,5 @@
+if (DEBUG) {
+    VERSION = VERSION + "-debug";
+}
+
+console.log(VERSION);
@@ -0,0 +1,7 @@
+function describe(VERSION) {
+    return "local " + VERSION;
+}
+
+const features = FEATURES.enabled.join("+");
+
+console.log(VERSION, typeof BUILD_NUMBER, BUILD_NUMBER + 1, DEBUG ? "debug" : "release", features, process.env.API_URL, describe("shadowed"));
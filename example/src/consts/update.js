@@ -0,0 +1,3 @@
+BUILD_NUMBER++;
+
+console.log(BUILD_NUMBER);
@@ -0,0 +1,5 @@
+import { bump } from "vendor_a";
+import { count } from "vendor_b";
+
+bump();
+console.log(count());
@@ -0,0 +1,7 @@
+"use client";
+
+import { save } from "./actions.js";
+
+export function button() {
+    return save;
+}
@@ -0,0 +1,3 @@
+import { button } from "./button.js";
+
+console.log(typeof button());
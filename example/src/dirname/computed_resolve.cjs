@@ -0,0 +1,3 @@
+const specifier = "./paths.cjs";
+
+console.log(require.resolve(specifier));
@@ -0,0 +1,8 @@
+let installed = true;
+try {
+    require.resolve("not_installed");
+} catch (error) {
+    installed = error.code;
+}
+
+console.log(require.resolve("./paths.cjs"), installed);
@@ -0,0 +1,3 @@
+import { dir, file, both } from "./paths.cjs";
+
+console.log(dir, file, both);
@@ -0,0 +1,7 @@
+const path = require("path");
+
+module.exports = {
+    dir: path.basename(__dirname),
+    file: path.basename(__filename),
+    both: { __dirname }.__dirname === __dirname,
+};
@@ -0,0 +1,3 @@
+const path = require("path");
+
+module.exports = path.basename(require.resolve("./paths.cjs"));
@@ -0,0 +1,3 @@
+import resolved from "./resolve.cjs";
+
+console.log(resolved);
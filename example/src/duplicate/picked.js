@@ -0,0 +1,2 @@
+export { name } from "./first.js";
+export * from "./second.js";
@@ -0,0 +1,2 @@
+export { name } from "./first.js";
+export { name } from "./second.js";
@@ -0,0 +1,3 @@
+import { name } from "./picked.js";
+
+console.log(name);
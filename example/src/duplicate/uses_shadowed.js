@@ -0,0 +1,3 @@
+import { name } from "./shadowed.js";
+
+console.log(name);
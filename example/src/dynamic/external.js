@@ -0,0 +1 @@
+import("lib").then(({ wibble }) => console.log(wibble()));
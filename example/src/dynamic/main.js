@@ -0,0 +1 @@
+import("./lazy.js").then(({ value }) => console.log(value));
@@ -0,0 +1,4 @@
+const add = new Function("a", "b", "return a + b");
+
+setTimeout("console.log('later')", 0);
+setTimeout(() => console.log(eval("add(1, 2)")), 0);
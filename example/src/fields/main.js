@@ -0,0 +1,3 @@
+import { field } from "fields";
+
+console.log(field);
@@ -0,0 +1,4 @@
+import locale from "moment/locale/fr";
+import { readFile } from "fs";
+
+console.log(typeof locale, typeof readFile);
@@ -0,0 +1 @@
+export function bibble() { return "wibble"; };
@@ -0,0 +1,4 @@
+import { wibble } from "lib";
+import { bibble } from "greetings/bibble.js";
+
+console.log(wibble(), bibble());
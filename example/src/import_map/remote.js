@@ -0,0 +1,3 @@
+import { remote } from "remote";
+
+console.log(remote());
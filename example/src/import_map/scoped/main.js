@@ -0,0 +1,3 @@
+import { bibble } from "greetings/bibble.js";
+
+console.log(bibble());
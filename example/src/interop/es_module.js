@@ -0,0 +1,3 @@
+import compiled from "./compiled.js";
+
+console.log(typeof compiled);
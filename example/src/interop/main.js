@@ -0,0 +1,3 @@
+import cjs from "cjs_lib";
+
+console.log(cjs.wobble());
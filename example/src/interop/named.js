@@ -0,0 +1,3 @@
+import { wobble } from "cjs_lib";
+
+console.log(wobble());
@@ -0,0 +1,11 @@
+let optional;
+try {
+    optional = require("not_installed");
+} catch (error) {
+    optional = error.code;
+}
+
+const local = process.env.NOT_SET ? require("./not_there.cjs") : "skipped";
+const lazy = () => require("./compiled.js");
+
+console.log(optional, local, typeof lazy());
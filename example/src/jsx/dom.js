@@ -0,0 +1,11 @@
+// A tiny createElement that renders straight to a string.
+export function h(tag, props, ...children) {
+    if (typeof tag === "function") {
+        return tag({ ...props, children });
+    }
+
+    const attrs = Object.entries(props || {}).map(([key, value]) => ` ${key}="${value}"`).join("");
+    return tag === Fragment ? children.join("") : `<${tag}${attrs}>${children.join("")}</${tag}>`;
+}
+
+export const Fragment = "fragment";
@@ -0,0 +1,13 @@
+import { h, Fragment } from "./dom.js";
+
+function Greeting({ name }) {
+    return <b>hello {name}</b>;
+}
+
+console.log(
+    <>
+        <p class="greeting" data-kind="jsx">
+            <Greeting name="jsx" />
+        </p>
+    </>
+);
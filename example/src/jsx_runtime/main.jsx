@@ -0,0 +1,11 @@
+function Greeting({ name }) {
+    return <b>hello {name}</b>;
+}
+
+console.log(
+    <>
+        <p key="first" class="greeting">
+            <Greeting name="runtime" />
+        </p>
+    </>
+);
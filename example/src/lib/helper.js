@@ -0,0 +1,5 @@
+export function helper(text) {
+	return text.toUpperCase();
+}
+
+export const unused = "unused";
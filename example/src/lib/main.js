@@ -0,0 +1,9 @@
+import { helper } from "./helper.js";
+
+export const version = "1.0.0";
+export { helper as help };
+export * from "./shapes.js";
+
+export default function greet(name) {
+	return helper(`hello ${name}`);
+}
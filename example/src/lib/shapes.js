@@ -0,0 +1,9 @@
+export function square(side) {
+	return side * side;
+}
+
+export class Circle {
+	constructor(radius) {
+		this.radius = radius;
+	}
+}
@@ -0,0 +1,6 @@
+function greet(name) {
+	const greeting = "hello";
+	return `${greeting} ${name}`;
+}
+
+console.log(greet.name, greet("mangle"));
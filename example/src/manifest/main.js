@@ -0,0 +1,3 @@
+import { source } from "manifested";
+
+console.log(source);
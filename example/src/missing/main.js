@@ -0,0 +1,3 @@
+import { bibble, wobble } from "../local.js";
+
+console.log(typeof wobble);
@@ -0,0 +1,4 @@
+import { host } from "peer_host";
+import { ok } from "peer_ok";
+
+console.log(host(), ok());
@@ -0,0 +1,3 @@
+import { built } from "peer_of_nightly";
+
+console.log(built());
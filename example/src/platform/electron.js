@@ -0,0 +1,4 @@
+import { app } from "electron";
+import { runtime } from "runtime_specific";
+
+app.whenReady().then(() => console.log(runtime));
@@ -0,0 +1,5 @@
+import { join } from "node:path";
+import { readFileSync } from "fs";
+import { runtime } from "runtime_specific";
+
+console.log(runtime, typeof join, typeof readFileSync);
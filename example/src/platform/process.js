@@ -0,0 +1,3 @@
+import { EventEmitter } from "events";
+
+console.log(process.type, typeof EventEmitter);
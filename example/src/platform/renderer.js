@@ -0,0 +1,3 @@
+import { runtime } from "runtime_specific";
+
+console.log(runtime);
@@ -0,0 +1,5 @@
+import { runtime } from "runtime_specific";
+
+const { sep } = require("path");
+
+console.log(runtime, sep);
@@ -0,0 +1,9 @@
+import { connect } from "cloudflare:sockets";
+import { Buffer } from "node:buffer";
+import { runtime } from "runtime_specific";
+
+export default {
+    fetch() {
+        return new Response(runtime + " " + typeof connect + " " + Buffer.byteLength("ok"));
+    },
+};
@@ -0,0 +1,14 @@
+const parts = [];
+
+// #if MOBILE
+parts.push("mobile");
+// #elif DESKTOP
+parts.push("desktop");
+    // #if !LEGACY
+parts.push("modern");
+    // #endif
+// #else
+parts.push("unknown");
+// #endif
+
+console.log(parts.join(" "));
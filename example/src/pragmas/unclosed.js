@@ -0,0 +1,2 @@
+// #if MOBILE
+console.log("never closed");
@@ -0,0 +1,3 @@
+import { greeting } from "https://esm.example.com/greet/index.js";
+
+console.log(greeting);
@@ -0,0 +1,6 @@
+import { wibble } from "lib";
+import mode from "./mode.cjs";
+
+const strict = (function () { return this === undefined; })();
+
+console.log(wibble(), strict ? "strict" : "sloppy", mode());
@@ -0,0 +1 @@
+import "./linked/lib.js";
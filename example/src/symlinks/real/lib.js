@@ -0,0 +1,3 @@
+import { sibling } from "../sibling.js";
+
+console.log(sibling);
@@ -0,0 +1 @@
+export const sibling = "real sibling";
@@ -0,0 +1,3 @@
+import shout from "./shout.upper";
+
+console.log(shout);
@@ -0,0 +1,11 @@
+import React from "./react.js";
+
+interface Props {
+    name: string;
+}
+
+function Greeting({ name }: Props) {
+    return <b>hello {name}</b>;
+}
+
+console.log(<><Greeting name="tsx" /></>);
@@ -0,0 +1,3 @@
+import { h, Fragment } from "../jsx/dom.js";
+
+export default { createElement: h, Fragment };
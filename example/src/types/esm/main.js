@@ -0,0 +1,3 @@
+const require = "esm";
+
+console.log(typeof this, require);
@@ -0,0 +1,17 @@
+import { Shape } from "./shapes.ts";
+
+enum Colour {
+    Red,
+    Green = 4,
+    Blue,
+    Named = "named",
+}
+
+const enum Flag {
+    None = 0,
+    A = 1 << 0,
+    B = 1 << 1,
+    Both = A | B,
+}
+
+console.log(Colour.Red, Colour[4], Colour.Blue, Colour.Named, Flag.Both, Shape.Square);
@@ -0,0 +1,5 @@
+export const enum Permission {
+    Read = 1,
+    Write = 2,
+    All = Read | Write,
+}
@@ -0,0 +1,13 @@
+export interface Greeting {
+    text: string;
+}
+
+export type Name = string;
+
+export class Greeter<T extends Name> {
+    constructor(private readonly greeting: string, public name?: T) {}
+
+    greet(this: Greeter<T>): Greeting {
+        return { text: `${this.greeting} ${this.name!}` };
+    }
+}
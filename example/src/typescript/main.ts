@@ -0,0 +1,7 @@
+import { Greeter, Greeting } from "./greeter.ts";
+import type { Name } from "./greeter.ts";
+
+const name: Name = "typescript";
+const greeting = new Greeter<Name>("hello", name).greet() as Greeting;
+
+console.log(greeting.text);
@@ -0,0 +1,5 @@
+namespace Shapes {
+    export const sides = 4;
+}
+
+console.log(Shapes.sides);
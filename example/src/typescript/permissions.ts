@@ -0,0 +1,5 @@
+import { Permission } from "./flags.ts";
+
+const Read = "read";
+
+console.log(Read, Permission.All, Permission["Write"]);
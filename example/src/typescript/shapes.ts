@@ -0,0 +1,4 @@
+export const enum Shape {
+    Circle = "circle",
+    Square = "square",
+}
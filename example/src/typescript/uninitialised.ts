@@ -0,0 +1,6 @@
+enum Direction {
+    Up = "up",
+    Down,
+}
+
+console.log(Direction.Down);
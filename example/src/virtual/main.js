@@ -0,0 +1,4 @@
+import info, { version } from "virtual:build-info";
+import { greeting } from "virtual:greeting";
+
+console.log(greeting, info.name, version);
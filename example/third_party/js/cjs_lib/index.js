@@ -0,0 +1,3 @@
+var esm = require("./esm.js");
+
+exports.wobble = function() { return "wobble" + esm.suffix; };
@@ -0,0 +1 @@
+export const field = "main";
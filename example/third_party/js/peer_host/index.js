@@ -0,0 +1,3 @@
+import { count } from "vendor_b";
+
+export function host() { return `host ${count()}`; }
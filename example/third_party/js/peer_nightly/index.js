@@ -0,0 +1 @@
+export function nightly() { return "nightly"; }
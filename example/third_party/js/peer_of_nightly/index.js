@@ -0,0 +1,3 @@
+import { nightly } from "peer_nightly";
+
+export function built() { return `built on ${nightly()}`; }
@@ -0,0 +1,3 @@
+import { count } from "vendor_b";
+
+export function ok() { return `ok ${count()}`; }
@@ -0,0 +1 @@
+export const runtime = "default";
@@ -0,0 +1,20 @@
+// The automatic JSX runtime, rendering straight to a string.
+export const Fragment = "fragment";
+
+export function jsx(tag, props, key) {
+    const { children = [], ...attrs } = props;
+    const rendered = [].concat(children).join("");
+
+    if (typeof tag === "function") {
+        return tag(props);
+    }
+    if (tag === Fragment) {
+        return rendered;
+    }
+
+    const keyAttr = key === undefined ? "" : ` key="${key}"`;
+    const attrString = Object.entries(attrs).map(([name, value]) => ` ${name}="${value}"`).join("");
+    return `<${tag}${keyAttr}${attrString}>${rendered}</${tag}>`;
+}
+
+export const jsxs = jsx;
@@ -0,0 +1,3 @@
+import { state } from "./util.js";
+
+export function bump() { state.count += 1; }
@@ -0,0 +1,3 @@
+import { state } from "./util.js";
+
+export function count() { return state.count; }
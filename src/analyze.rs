@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use swc_common::{SourceMap, Span, Spanned};
+use swc_ecma_ast::{
+    Decl, ExportNamedSpecifier, ExportSpecifier, Expr, ImportSpecifier, Lit, Module, ModuleDecl, ModuleExportName, ModuleItem, ObjectPatProp,
+    Pat, Stmt,
+};
+
+use crate::graph::ModuleGraph;
+
+/// The static import/export surface of a loaded module.
+#[derive(Default)]
+pub struct ModuleInfo {
+    pub exports: BTreeSet<String>,
+    /// Specifiers of `export * from "..."`.
+    pub star_exports: Vec<String>,
+    pub imports: Vec<NamedImport>,
+    /// Names exported more than once, with where they're exported again.
+    pub duplicate_exports: Vec<String>,
+    /// The directives the module starts with, such as `"use client"`.
+    pub directives: Vec<String>,
+}
+
+impl ModuleInfo {
+    fn export(&mut self, cm: &SourceMap, name: String, span: Span) {
+        if self.exports.contains(&name) {
+            self.duplicate_exports.push(format!("{}: '{name}' is already exported", location(cm, span)));
+        } else {
+            self.exports.insert(name);
+        }
+    }
+}
+
+/// A binding imported (or re-exported) by name from another module.
+pub struct NamedImport {
+    pub specifier: String,
+    pub name: String,
+    pub location: String,
+}
+
+pub fn module_info(cm: &SourceMap, module: &Module) -> ModuleInfo {
+    // Imports the loader itself adds can come before the directives.
+    let directives = module.body.iter()
+        .filter(|item| !matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+        .map_while(|item| match item {
+            ModuleItem::Stmt(Stmt::Expr(stmt)) => match &*stmt.expr {
+                Expr::Lit(Lit::Str(directive)) => Some(directive.value.to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    let mut info = ModuleInfo { directives, ..Default::default() };
+
+    for item in &module.body {
+        let decl = match item {
+            ModuleItem::ModuleDecl(decl) => decl,
+            ModuleItem::Stmt(_) => continue,
+        };
+
+        match decl {
+            ModuleDecl::Import(import) if !import.type_only => {
+                for specifier in &import.specifiers {
+                    let (name, span) = match specifier {
+                        ImportSpecifier::Named(named) => match &named.imported {
+                            Some(imported) => (export_name(imported), named.span),
+                            None => (named.local.sym.to_string(), named.span),
+                        },
+                        ImportSpecifier::Default(default) => (String::from("default"), default.span),
+                        ImportSpecifier::Namespace(_) => continue,
+                    };
+
+                    info.imports.push(NamedImport {
+                        specifier: import.src.value.to_string(),
+                        name,
+                        location: location(cm, span),
+                    });
+                }
+            },
+            ModuleDecl::ExportDecl(export) => match &export.decl {
+                Decl::Class(class) => info.export(cm, class.ident.sym.to_string(), class.ident.span),
+                Decl::Fn(function) => info.export(cm, function.ident.sym.to_string(), function.ident.span),
+                Decl::Var(var) => {
+                    for declarator in &var.decls {
+                        let mut names = BTreeSet::new();
+                        pat_names(&declarator.name, &mut names);
+                        for name in names {
+                            info.export(cm, name, declarator.span);
+                        }
+                    }
+                },
+                _ => {},
+            },
+            ModuleDecl::ExportNamed(export) if !export.type_only => {
+                for specifier in &export.specifiers {
+                    match specifier {
+                        ExportSpecifier::Named(named) => {
+                            let exported = named.exported.as_ref().unwrap_or(&named.orig);
+                            info.export(cm, export_name(exported), named.span);
+
+                            if let Some(src) = &export.src {
+                                info.imports.push(NamedImport {
+                                    specifier: src.value.to_string(),
+                                    name: export_name(&named.orig),
+                                    location: location(cm, named.span),
+                                });
+                            }
+                        },
+                        ExportSpecifier::Namespace(namespace) => info.export(cm, export_name(&namespace.name), namespace.span),
+                        ExportSpecifier::Default(default) => info.export(cm, default.exported.sym.to_string(), default.exported.span),
+                    }
+                }
+            },
+            ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_) => {
+                info.export(cm, String::from("default"), decl.span());
+            },
+            ModuleDecl::ExportAll(export) => info.star_exports.push(export.src.value.to_string()),
+            _ => {},
+        }
+    }
+
+    info
+}
+
+/// Every named import whose target is known to lack the export, as
+/// messages naming both the importer's location and the target.
+pub fn missing_exports(modules: &BTreeMap<String, ModuleInfo>, graph: &ModuleGraph) -> Vec<String> {
+    let mut errors = vec![];
+
+    for (file, info) in modules {
+        for import in &info.imports {
+            let target = match graph.resolution(file, &import.specifier) {
+                Some(target) => target,
+                None => continue,
+            };
+
+            if let Some(exports) = exports_of(modules, graph, &target, &mut BTreeSet::new()) {
+                if !exports.contains(&import.name) {
+                    errors.push(format!("{}: '{}' is not exported by {}", import.location, import.name, target));
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+/// How a bundle's exports differ from those of the entry it was bundled
+/// from, as messages. `export *` of modules that were bundled counts as the
+/// names they export, of anything else as the star export itself.
+pub fn export_changes(modules: &BTreeMap<String, ModuleInfo>, graph: &ModuleGraph, entry: &str, bundle: &ModuleInfo) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    let mut stars = BTreeSet::new();
+    entry_surface(modules, graph, entry, &mut names, &mut stars, &mut BTreeSet::new());
+
+    let bundle_stars: BTreeSet<String> = bundle.star_exports.iter().cloned().collect();
+
+    let mut changes = vec![];
+    changes.extend(names.difference(&bundle.exports).map(|name| format!("'{name}' is no longer exported")));
+    changes.extend(bundle.exports.difference(&names).map(|name| format!("'{name}' is exported but the entry doesn't export it")));
+    changes.extend(stars.difference(&bundle_stars).map(|star| format!("export * from '{star}' is gone")));
+    changes.extend(bundle_stars.difference(&stars).map(|star| format!("export * from '{star}' is new")));
+    changes
+}
+
+/// Names the entry `file` gets through `export *` from more than one
+/// module, as messages naming the modules. A name a module exports itself
+/// shadows any it would get through its star exports, as in ES, but two
+/// star exports of one name conflict and the bundler exports it once per
+/// module, leaving a bundle that fails to load.
+pub fn export_collisions(modules: &BTreeMap<String, ModuleInfo>, graph: &ModuleGraph, entry: &str) -> Vec<String> {
+    export_origins(modules, graph, entry, &mut BTreeMap::new(), &mut BTreeSet::new())
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|(name, files)| format!("'{name}' is exported by {}", files.into_iter().collect::<Vec<_>>().join(" and ")))
+        .collect()
+}
+
+/// The names `export *` would bring into the entry `file` from each module
+/// but that are shadowed on the way, by the modules they'd come from.
+pub fn shadowed_exports(modules: &BTreeMap<String, ModuleInfo>, graph: &ModuleGraph, entry: &str) -> BTreeMap<String, BTreeSet<String>> {
+    let mut shadowed = BTreeMap::new();
+    export_origins(modules, graph, entry, &mut shadowed, &mut BTreeSet::new());
+    shadowed
+}
+
+/// The bundler exports a name from every module it would come from
+/// whether or not it's shadowed, so the exports of the bundle of an entry
+/// whose binding was declared in a module in `shadowed` are dropped while
+/// another export of the name is left.
+pub fn drop_shadowed_exports(cm: &SourceMap, mut module: Module, shadowed: &BTreeMap<String, BTreeSet<String>>) -> Module {
+    if shadowed.is_empty() {
+        return module;
+    }
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for item in &module.body {
+        if let ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) = item {
+            for specifier in &export.specifiers {
+                if let ExportSpecifier::Named(named) = specifier {
+                    *counts.entry(export_name(named.exported.as_ref().unwrap_or(&named.orig))).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    module.body.retain_mut(|item| {
+        let export = match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) if export.src.is_none() && !export.specifiers.is_empty() => export,
+            _ => return true,
+        };
+
+        export.specifiers.retain(|specifier| {
+            let (named, orig) = match specifier {
+                ExportSpecifier::Named(named @ ExportNamedSpecifier { orig: ModuleExportName::Ident(orig), .. }) => (named, orig),
+                _ => return true,
+            };
+
+            let name = export_name(named.exported.as_ref().unwrap_or(&named.orig));
+            let drop = !orig.span.is_dummy()
+                && counts.get(&name).is_some_and(|count| *count > 1)
+                && shadowed.get(&name).is_some_and(|files| files.contains(&cm.lookup_char_pos(orig.span.lo).file.name.to_string()));
+            if drop {
+                counts.entry(name).and_modify(|count| *count -= 1);
+            }
+            !drop
+        });
+        !export.specifiers.is_empty()
+    });
+
+    module
+}
+
+/// The modules each name `file` exports is declared in, noting the names
+/// its own exports shadow in `shadowed`.
+fn export_origins(
+    modules: &BTreeMap<String, ModuleInfo>,
+    graph: &ModuleGraph,
+    file: &str,
+    shadowed: &mut BTreeMap<String, BTreeSet<String>>,
+    visiting: &mut BTreeSet<String>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let info = match modules.get(file) {
+        Some(info) if visiting.insert(file.to_string()) => info,
+        _ => return BTreeMap::new(),
+    };
+
+    let mut origins: BTreeMap<String, BTreeSet<String>> = info.exports.iter()
+        .map(|name| (name.clone(), BTreeSet::from([file.to_string()])))
+        .collect();
+
+    for specifier in &info.star_exports {
+        if let Some(target) = graph.resolution(file, specifier) {
+            for (name, files) in export_origins(modules, graph, &target, shadowed, visiting) {
+                if name == "default" {
+                    continue;
+                }
+                if info.exports.contains(&name) {
+                    shadowed.entry(name).or_default().extend(files);
+                } else {
+                    origins.entry(name).or_default().extend(files);
+                }
+            }
+        }
+    }
+
+    visiting.remove(file);
+    origins
+}
+
+/// The chains of imports that lead from `entry` to modules starting with
+/// `"use server"`, which a client bundle mustn't take with it.
+pub fn server_modules(modules: &BTreeMap<String, ModuleInfo>, graph: &ModuleGraph, entry: &str) -> Vec<Vec<String>> {
+    modules.iter()
+        .filter(|(_, info)| info.directives.iter().any(|directive| directive == "use server"))
+        .filter_map(|(file, _)| graph.chain(entry, file))
+        .collect()
+}
+
+fn entry_surface(
+    modules: &BTreeMap<String, ModuleInfo>,
+    graph: &ModuleGraph,
+    file: &str,
+    names: &mut BTreeSet<String>,
+    stars: &mut BTreeSet<String>,
+    seen: &mut BTreeSet<String>,
+) {
+    let info = match modules.get(file) {
+        Some(info) if seen.insert(file.to_string()) => info,
+        _ => return,
+    };
+
+    let is_entry = seen.len() == 1;
+    names.extend(info.exports.iter().filter(|name| is_entry || *name != "default").cloned());
+
+    for specifier in &info.star_exports {
+        match graph.resolution(file, specifier).filter(|target| modules.contains_key(target)) {
+            Some(target) => entry_surface(modules, graph, &target, names, stars, seen),
+            None => { stars.insert(specifier.clone()); },
+        }
+    }
+}
+
+/// All the names `file` exports, following `export *`. `None` when that
+/// can't be known, e.g. a star export of an external module.
+fn exports_of(
+    modules: &BTreeMap<String, ModuleInfo>,
+    graph: &ModuleGraph,
+    file: &str,
+    seen: &mut BTreeSet<String>,
+) -> Option<BTreeSet<String>> {
+    let info = modules.get(file)?;
+    let mut exports = info.exports.clone();
+
+    if !seen.insert(file.to_string()) {
+        return Some(exports);
+    }
+
+    for specifier in &info.star_exports {
+        let target = graph.resolution(file, specifier)?;
+        let star = exports_of(modules, graph, &target, seen)?;
+        exports.extend(star.into_iter().filter(|name| name != "default"));
+    }
+
+    Some(exports)
+}
+
+fn export_name(name: &ModuleExportName) -> String {
+    match name {
+        ModuleExportName::Ident(ident) => ident.sym.to_string(),
+        ModuleExportName::Str(s) => s.value.to_string(),
+    }
+}
+
+fn pat_names(pat: &Pat, names: &mut BTreeSet<String>) {
+    match pat {
+        Pat::Ident(ident) => { names.insert(ident.id.sym.to_string()); },
+        Pat::Array(array) => {
+            for elem in array.elems.iter().flatten() {
+                pat_names(elem, names);
+            }
+        },
+        Pat::Object(object) => {
+            for prop in &object.props {
+                match prop {
+                    ObjectPatProp::KeyValue(kv) => pat_names(&kv.value, names),
+                    ObjectPatProp::Assign(assign) => { names.insert(assign.key.sym.to_string()); },
+                    ObjectPatProp::Rest(rest) => pat_names(&rest.arg, names),
+                }
+            }
+        },
+        Pat::Rest(rest) => pat_names(&rest.arg, names),
+        Pat::Assign(assign) => pat_names(&assign.left, names),
+        _ => {},
+    }
+}
+
+pub fn location(cm: &SourceMap, span: Span) -> String {
+    if span.is_dummy() {
+        return String::from("<generated>");
+    }
+
+    let loc = cm.lookup_char_pos(span.lo);
+    format!("{}:{}:{}", loc.file.name, loc.line, loc.col_display + 1)
+}
@@ -0,0 +1,61 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{Error, anyhow, bail};
+
+/// A precompressed copy written next to each output.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Brotli,
+}
+
+impl Compression {
+    /// The extension appended to the output's name.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+            Compression::Brotli => ".br",
+        }
+    }
+
+    /// Compresses `contents` at the highest level with the `gzip` or
+    /// `brotli` command. gzip is told to leave out the name and time, so the
+    /// same contents always compress the same.
+    pub fn compress(&self, contents: &[u8]) -> Result<Vec<u8>, Error> {
+        let (program, args): (&str, &[&str]) = match self {
+            Compression::Gzip => ("gzip", &["-9", "-n", "-c"]),
+            Compression::Brotli => ("brotli", &["-q", "11", "-c"]),
+        };
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|why| match why.kind() {
+                std::io::ErrorKind::NotFound => anyhow!("--compress {program} needs {program} to be installed"),
+                _ => anyhow!("failed to run {program}: {why}"),
+            })?;
+
+        // The input is written from another thread so a large output can't
+        // fill the pipe while we're still writing.
+        let mut stdin = child.stdin.take();
+        let output = std::thread::scope(|scope| {
+            let writer = scope.spawn(move || match stdin.as_mut() {
+                Some(stdin) => stdin.write_all(contents),
+                None => Ok(()),
+            });
+            let output = child.wait_with_output();
+            writer.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+            output
+        })?;
+
+        if !output.status.success() {
+            bail!("{program} failed with {}", output.status);
+        }
+
+        Ok(output.stdout)
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use anyhow::{Error, anyhow, bail};
+
+use swc_common::{sync::Lrc, SourceMap};
+use swc_ecma_ast::{CallExpr, Callee, Expr, Lit, ModuleItem, Module, Stmt, Tpl};
+use swc_ecma_visit::{VisitMut, VisitMutWith};
+
+use crate::{analyze, interop, paths};
+
+/// What's done with `import()`, and `require()` in CommonJS, of a
+/// specifier that isn't a string literal, which the bundler can't follow.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputedSpecifiers {
+    /// Leave them as they are, with a warning.
+    Warn,
+    /// Fail the build.
+    Error,
+    /// Expand a template literal of a relative path, such as
+    /// `./locales/${lang}.js`, into the files matching it, each of which is
+    /// bundled. Anything else is left with a warning.
+    Glob,
+}
+
+/// Applies `mode` to the computed specifiers of `module`, which was loaded
+/// from `path`. `require()` is only looked at in CommonJS modules.
+pub fn expand(cm: &Lrc<SourceMap>, module: &mut Module, path: &Path, commonjs: bool, mode: ComputedSpecifiers) -> Result<(), Error> {
+    let mut expander = Expander { cm, dir: path.parent().unwrap_or(Path::new("")), commonjs, mode, error: None };
+    module.visit_mut_with(&mut expander);
+
+    match expander.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+struct Expander<'a> {
+    cm: &'a Lrc<SourceMap>,
+    dir: &'a Path,
+    commonjs: bool,
+    mode: ComputedSpecifiers,
+    error: Option<Error>,
+}
+
+impl VisitMut for Expander<'_> {
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+
+        let kind = match &call.callee {
+            Callee::Import(_) => "import",
+            Callee::Expr(callee) if self.commonjs && matches!(&**callee, Expr::Ident(ident) if &*ident.sym == "require") => "require",
+            _ => return,
+        };
+
+        let pattern = match call.args.as_slice() {
+            [arg] if arg.spread.is_none() => match &*arg.expr {
+                Expr::Lit(Lit::Str(_)) => return,
+                Expr::Tpl(tpl) if self.mode == ComputedSpecifiers::Glob => relative_pattern(tpl),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let message = || format!("{}: {kind}() of a computed specifier can't be followed", analyze::location(self.cm, call.span));
+        let pattern = match (pattern, self.mode) {
+            (Some(pattern), _) => pattern,
+            (None, ComputedSpecifiers::Error) => {
+                self.error.get_or_insert_with(|| anyhow!("{}", message()));
+                return;
+            },
+            (None, _) => {
+                eprintln!("warning: {}, it's left as it is", message());
+                return;
+            },
+        };
+
+        match candidates(self.dir, &pattern).and_then(|candidates| dispatch(self.cm, kind, &candidates)) {
+            Ok(dispatch) => call.callee = Callee::Expr(Box::new(dispatch)),
+            Err(error) => { self.error.get_or_insert(error); },
+        }
+    }
+}
+
+/// A glob matching every specifier a template literal of a relative path
+/// could produce, each of its expressions standing in for `*`.
+fn relative_pattern(tpl: &Tpl) -> Option<String> {
+    let quasis = tpl.quasis.iter()
+        .map(|quasi| quasi.cooked.as_ref().map(|cooked| glob::Pattern::escape(cooked)))
+        .collect::<Option<Vec<String>>>()?;
+
+    let pattern = quasis.join("*");
+    (pattern.starts_with("./") || pattern.starts_with("../")).then_some(pattern)
+}
+
+/// The specifiers, relative to `dir`, of the files matching `pattern`.
+fn candidates(dir: &Path, pattern: &str) -> Result<Vec<String>, Error> {
+    let mut specifiers = vec![];
+    for path in glob::glob(&paths::to_slash(&dir.join(pattern)))? {
+        let path = path?;
+        let relative = paths::to_slash(path.strip_prefix(dir)?);
+        specifiers.push(if relative.starts_with("../") { relative } else { format!("./{relative}") });
+    }
+
+    if specifiers.is_empty() {
+        bail!("no files match {pattern:?} in {}", dir.display());
+    }
+    specifiers.sort();
+    Ok(specifiers)
+}
+
+/// A function taking a specifier to the `kind` call of the matching
+/// candidate, so each of them is a literal the bundler can follow.
+fn dispatch(cm: &Lrc<SourceMap>, kind: &str, candidates: &[String]) -> Result<Expr, Error> {
+    let mut src = String::from("(function (specifier) {\n    switch (specifier) {\n");
+    for candidate in candidates {
+        src.push_str(&format!("        case {candidate:?}: return {kind}({candidate:?});\n"));
+    }
+    src.push_str("    }\n");
+    src.push_str(match kind {
+        "import" => "    return Promise.reject(new Error(\"Cannot find module '\" + specifier + \"'\"));\n",
+        _ => "    throw new Error(\"Cannot find module '\" + specifier + \"'\");\n",
+    });
+    src.push_str("});\n");
+
+    let module = interop::parse_generated(cm, "please-bundle:computed-specifier", src)?;
+    match module.body.into_iter().next() {
+        Some(ModuleItem::Stmt(Stmt::Expr(stmt))) => Ok(*stmt.expr),
+        _ => bail!("failed to parse the dispatch for {kind}()"),
+    }
+}
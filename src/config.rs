@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use anyhow::{Error, anyhow, bail};
+use serde_json::{Map, Value};
+
+/// The arguments of every build a config file describes, each made of the
+/// options in the config followed by those given on the command line, so
+/// the latter win for options that take one value.
+///
+/// A config is a JSON object keyed by long option names: `true` turns a
+/// flag on, arrays repeat an option, objects give `name=value` pairs and
+/// `"inputs"` lists the entries. Entries under `"entries"` are each built
+/// on their own, with whatever options they give replacing the config's:
+///
+/// ```json
+/// {
+///     "outdir": "dist",
+///     "entries": {
+///         "main": { "entry": "src/main.js" },
+///         "worker": { "entry": "src/worker.js", "format": "system" }
+///     }
+/// }
+/// ```
+///
+/// A config can also be an array of such objects, for builds that have
+/// nothing in common, such as a library's ES module and System.register
+/// builds. They're built in the same run, as many at once as --jobs allows.
+pub fn builds(path: &Path, cli: &[String]) -> Result<Vec<Vec<String>>, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|why| anyhow!("failed to read config {path:?}: {why}"))?;
+    let config: Value = serde_json::from_str(&contents)
+        .map_err(|why| anyhow!("failed to parse config {path:?}: {why}"))?;
+
+    let configs = match config {
+        Value::Array(configs) => configs,
+        config => vec![config],
+    };
+
+    let mut builds = vec![];
+    for config in configs {
+        builds.extend(config_builds(path, config, cli)?);
+    }
+    Ok(builds)
+}
+
+fn config_builds(path: &Path, config: Value, cli: &[String]) -> Result<Vec<Vec<String>>, Error> {
+    let mut options = match config {
+        Value::Object(options) => options,
+        _ => bail!("config {path:?} should be an object of options, or an array of them"),
+    };
+
+    let entries = match options.remove("entries") {
+        None => Map::new(),
+        Some(Value::Object(entries)) => entries,
+        Some(_) => bail!("\"entries\" in config {path:?} should be an object of entry names to options"),
+    };
+
+    let (program, cli) = match cli.split_first() {
+        Some((program, rest)) => (program.clone(), rest),
+        None => (String::from("please-bundle"), &[][..]),
+    };
+    let build = |options: &Map<String, Value>| -> Result<Vec<String>, Error> {
+        let mut args = vec![program.clone()];
+        args.extend(options_to_args(options)?);
+        args.extend(cli.iter().cloned());
+        Ok(args)
+    };
+
+    if entries.is_empty() {
+        return Ok(vec![build(&options)?]);
+    }
+
+    let mut builds = vec![];
+    if options.contains_key("inputs") || options.contains_key("input") {
+        builds.push(build(&options)?);
+    }
+
+    let mut shared = options;
+    shared.remove("inputs");
+    shared.remove("input");
+
+    for (name, overrides) in entries {
+        let mut overrides = match overrides {
+            Value::Object(overrides) => overrides,
+            _ => bail!("entry {name:?} in config {path:?} should be an object of options"),
+        };
+        let entry = match overrides.remove("entry") {
+            Some(Value::String(entry)) => entry,
+            _ => bail!("entry {name:?} in config {path:?} needs an \"entry\" path"),
+        };
+
+        let mut options = shared.clone();
+        options.extend(overrides);
+        options.insert(String::from("input"), Value::Object(Map::from_iter([(name, Value::String(entry))])));
+        builds.push(build(&options)?);
+    }
+
+    Ok(builds)
+}
+
+/// The path given with `--config`, if any, and the command line without it.
+pub fn take_config(args: &[String]) -> (Option<String>, Vec<String>) {
+    let mut path = None;
+    let mut kept = vec![];
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            path = args.next().cloned();
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            path = Some(value.to_string());
+        } else {
+            kept.push(arg.clone());
+        }
+    }
+
+    (path, kept)
+}
+
+fn options_to_args(options: &Map<String, Value>) -> Result<Vec<String>, Error> {
+    let mut args = vec![];
+
+    for (name, value) in options {
+        match name.as_str() {
+            "config" => bail!("a config can't name another config"),
+            "inputs" => match value {
+                Value::Array(inputs) => args.extend(inputs.iter().map(value_to_string)),
+                _ => args.push(value_to_string(value)),
+            },
+            _ => push_option(&mut args, name, value),
+        }
+    }
+
+    Ok(args)
+}
+
+fn push_option(args: &mut Vec<String>, name: &str, value: &Value) {
+    match value {
+        Value::Null | Value::Bool(false) => {},
+        Value::Bool(true) => args.push(format!("--{name}")),
+        Value::Array(values) => {
+            for value in values {
+                push_option(args, name, value);
+            }
+        },
+        Value::Object(pairs) => {
+            for (key, value) in pairs {
+                args.push(format!("--{name}={key}={}", value_to_string(value)));
+            }
+        },
+        _ => args.push(format!("--{name}={}", value_to_string(value))),
+    }
+}
+
+/// Strings as they are, anything else as JSON.
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        _ => value.to_string(),
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{Error, bail};
+
+use swc_common::{Globals, Mark, SourceMap, Span, SyntaxContext, DUMMY_SP, GLOBALS};
+use swc_ecma_ast::{
+    ArrayLit, AssignExpr, Bool, Expr, ExprOrSpread, Ident, KeyValueProp, Lit, MemberProp, Module, Null, Number,
+    ObjectLit, Pat, PatOrExpr, Prop, PropName, PropOrSpread, Str, UnaryExpr, UnaryOp, UpdateExpr,
+};
+use swc_ecma_transforms_base::resolver;
+use swc_ecma_visit::{FoldWith, VisitMut, VisitMutWith};
+
+use crate::{analyze, interop};
+
+/// A typed build-time constant, from `--const NAME:type=value`, where
+/// `NAME` is a global such as `VERSION` or a path through one such as
+/// `process.env.API_URL`.
+#[derive(Clone, Debug)]
+pub struct Const {
+    pub path: Vec<String>,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Json(serde_json::Value),
+}
+
+impl FromStr for Const {
+    type Err = Error;
+
+    fn from_str(arg: &str) -> Result<Self, Error> {
+        let (name, kind, value) = match arg.split_once('=').map(|(name, value)| (name.rsplit_once(':'), value)) {
+            Some((Some((name, kind)), value)) => (name, kind, value),
+            _ => bail!("expected NAME:type=value, got {arg:?}"),
+        };
+
+        let path: Vec<String> = name.split('.').map(String::from).collect();
+        if !path.iter().all(|part| interop::is_identifier_name(part)) {
+            bail!("{name:?} isn't a name or a path through one, such as process.env.NAME");
+        }
+
+        let value = match kind {
+            "string" => Value::String(value.to_string()),
+            "number" => match value.parse::<f64>() {
+                Ok(number) if number.is_finite() => Value::Number(number),
+                _ => bail!("{name} is a number but {value:?} isn't one"),
+            },
+            "bool" => match value {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => bail!("{name} is a bool but {value:?} is neither true nor false"),
+            },
+            "json" => match serde_json::from_str(value) {
+                Ok(json) => Value::Json(json),
+                Err(why) => bail!("{name} is json but {value:?} isn't: {why}"),
+            },
+            _ => bail!("{name} has type {kind:?}, expected string, number, bool or json"),
+        };
+
+        Ok(Const { path, value })
+    }
+}
+
+/// Replaces every reference to the globals named by `consts` with their
+/// values, `{ NAME }` included. Later constants of the same name win over
+/// earlier ones, and assigning to one is an error, as it is to a `const`.
+pub fn inline(globals: &Globals, cm: &SourceMap, module: Module, consts: &[Const]) -> Result<Module, Error> {
+    if consts.is_empty() {
+        return Ok(module);
+    }
+
+    let values: HashMap<&[String], &Value> = consts.iter().map(|c| (c.path.as_slice(), &c.value)).collect();
+
+    GLOBALS.set(globals, || {
+        let unresolved_mark = Mark::new();
+        let mut module = module.fold_with(&mut resolver(unresolved_mark, Mark::new(), false));
+        let mut inliner = ConstInliner { unresolved: SyntaxContext::empty().apply_mark(unresolved_mark), values, assigned: None };
+        module.visit_mut_with(&mut inliner);
+
+        if let Some((path, span)) = inliner.assigned {
+            bail!("{}: {} is set with --const, so it can't be assigned to", analyze::location(cm, span), path.join("."));
+        }
+        Ok(module)
+    })
+}
+
+struct ConstInliner<'a> {
+    unresolved: SyntaxContext,
+    values: HashMap<&'a [String], &'a Value>,
+    /// The first constant found assigned to, and where.
+    assigned: Option<(Vec<String>, Span)>,
+}
+
+impl ConstInliner<'_> {
+    /// The path of globals and properties `expr` reads, if it's one.
+    fn global_path(&self, expr: &Expr) -> Option<Vec<String>> {
+        match expr {
+            Expr::Ident(ident) if ident.span.ctxt == self.unresolved => Some(vec![ident.sym.to_string()]),
+            Expr::Member(member) => match &member.prop {
+                MemberProp::Ident(prop) => {
+                    let mut path = self.global_path(&member.obj)?;
+                    path.push(prop.sym.to_string());
+                    Some(path)
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn note_if_constant(&mut self, target: &Expr, span: Span) {
+        if let Some(path) = self.global_path(target).filter(|path| self.values.contains_key(path.as_slice())) {
+            self.assigned.get_or_insert((path, span));
+        }
+    }
+}
+
+impl VisitMut for ConstInliner<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Some(value) = self.global_path(expr).and_then(|path| self.values.get(path.as_slice())) {
+            *expr = value_expr(value);
+            return;
+        }
+
+        expr.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_assign_expr(&mut self, assign: &mut AssignExpr) {
+        match &assign.left {
+            PatOrExpr::Expr(target) => self.note_if_constant(target, assign.span),
+            PatOrExpr::Pat(target) => match &**target {
+                Pat::Ident(target) => self.note_if_constant(&Expr::Ident(target.id.clone()), assign.span),
+                Pat::Expr(target) => self.note_if_constant(target, assign.span),
+                _ => {},
+            },
+        }
+        assign.right.visit_mut_with(self);
+    }
+
+    fn visit_mut_update_expr(&mut self, update: &mut UpdateExpr) {
+        self.note_if_constant(&update.arg, update.span);
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(name) = prop {
+            if let Some(value) = self.global_path(&Expr::Ident(name.clone())).and_then(|path| self.values.get(path.as_slice())) {
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(Ident::new(name.sym.clone(), name.span.with_ctxt(SyntaxContext::empty()))),
+                    value: Box::new(value_expr(value)),
+                });
+                return;
+            }
+        }
+
+        prop.visit_mut_children_with(self);
+    }
+}
+
+fn value_expr(value: &Value) -> Expr {
+    match value {
+        Value::String(s) => string(s),
+        Value::Number(n) => number(*n),
+        Value::Bool(b) => Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: *b })),
+        Value::Json(json) => json_expr(json),
+    }
+}
+
+fn json_expr(json: &serde_json::Value) -> Expr {
+    match json {
+        serde_json::Value::Null => Expr::Lit(Lit::Null(Null { span: DUMMY_SP })),
+        serde_json::Value::Bool(b) => Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: *b })),
+        serde_json::Value::Number(n) => number(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => string(s),
+        serde_json::Value::Array(items) => Expr::Array(ArrayLit {
+            span: DUMMY_SP,
+            elems: items.iter().map(|item| Some(ExprOrSpread { spread: None, expr: Box::new(json_expr(item)) })).collect(),
+        }),
+        serde_json::Value::Object(entries) => Expr::Object(ObjectLit {
+            span: DUMMY_SP,
+            props: entries.iter()
+                .map(|(key, value)| PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                    key: PropName::Str(Str { span: DUMMY_SP, value: key.as_str().into(), raw: None }),
+                    value: Box::new(json_expr(value)),
+                }))))
+                .collect(),
+        }),
+    }
+}
+
+fn string(s: &str) -> Expr {
+    Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: s.into(), raw: None }))
+}
+
+/// Negative numbers are negated literals, as the codegen expects.
+fn number(n: f64) -> Expr {
+    let literal = Expr::Lit(Lit::Num(Number { span: DUMMY_SP, value: n.abs(), raw: None }));
+    if n.is_sign_negative() && n != 0.0 {
+        Expr::Unary(UnaryExpr { span: DUMMY_SP, op: UnaryOp::Minus, arg: Box::new(literal) })
+    } else {
+        literal
+    }
+}
@@ -0,0 +1,76 @@
+use swc_common::{SourceMap, Span, Spanned};
+use swc_ecma_ast::{CallExpr, Callee, Expr, ExprOrSpread, Lit, MemberProp, Module, NewExpr};
+use swc_ecma_visit::{Visit, VisitWith};
+
+use crate::analyze;
+
+/// Every place `module` evaluates a string as code, which a
+/// Content-Security-Policy without `'unsafe-eval'` refuses: `eval`,
+/// `Function` called or constructed, and timers given a string rather
+/// than a function. Code the bundler generates is checked along with the
+/// rest, and shows up under its `please-bundle:` name.
+pub fn eval_uses(cm: &SourceMap, module: &Module) -> Vec<String> {
+    let mut finder = EvalFinder { cm, uses: vec![] };
+    module.visit_with(&mut finder);
+    finder.uses
+}
+
+struct EvalFinder<'a> {
+    cm: &'a SourceMap,
+    uses: Vec<String>,
+}
+
+impl EvalFinder<'_> {
+    fn report(&mut self, span: Span, what: &str) {
+        self.uses.push(format!("{}: {what}", analyze::location(self.cm, span)));
+    }
+}
+
+impl Visit for EvalFinder<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if global_name(expr) == Some("eval") {
+            self.report(expr.span(), "eval");
+        }
+
+        expr.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Expr(callee) = &call.callee {
+            match global_name(callee) {
+                Some("Function") => self.report(call.span, "Function()"),
+                Some(timer @ ("setTimeout" | "setInterval" | "setImmediate")) if is_string(call.args.first()) => {
+                    self.report(call.span, &format!("{timer}() with a string"));
+                },
+                _ => {},
+            }
+        }
+
+        call.visit_children_with(self);
+    }
+
+    fn visit_new_expr(&mut self, new: &NewExpr) {
+        if global_name(&new.callee) == Some("Function") {
+            self.report(new.span, "new Function()");
+        }
+
+        new.visit_children_with(self);
+    }
+}
+
+/// The name of a global `expr` refers to, either directly or as a
+/// property of `globalThis`, `window` or `self`.
+fn global_name(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Ident(ident) => Some(&ident.sym),
+        Expr::Member(member) => match (&*member.obj, &member.prop) {
+            (Expr::Ident(obj), MemberProp::Ident(prop)) if matches!(&*obj.sym, "globalThis" | "window" | "self") => Some(&prop.sym),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_string(arg: Option<&ExprOrSpread>) -> bool {
+    matches!(arg.map(|arg| &*arg.expr), Some(Expr::Lit(Lit::Str(_)) | Expr::Tpl(_)))
+}
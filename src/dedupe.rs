@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+
+use sha2::{Digest, Sha256};
+
+use swc_common::{sync::{Lock, Lrc}, FileName, SourceMap, FilePathMapping};
+use swc_ecma_ast::{Callee, CallExpr, EsVersion, ModuleDecl, ModuleItem};
+use swc_ecma_parser::{parse_file_as_module, EsConfig, Syntax};
+use swc_ecma_visit::{Visit, VisitWith};
+
+use crate::interop;
+
+/// Spots modules that are byte-for-byte identical, whether that's the same
+/// file reached through a symlink or a copy vendored by two packages, so
+/// they can be bundled once.
+///
+/// Only modules without dependencies are merged: identical code that
+/// imports relative paths can still resolve them to different files.
+#[derive(Default)]
+pub struct Deduper {
+    by_hash: Lock<HashMap<Vec<u8>, PathBuf>>,
+    representatives: Lock<HashMap<PathBuf, PathBuf>>,
+}
+
+impl Deduper {
+    /// The path the module at `path` should be bundled as.
+    pub fn representative(&self, path: &Path) -> Result<PathBuf, Error> {
+        if let Some(representative) = self.representatives.borrow().get(path) {
+            return Ok(representative.clone());
+        }
+
+        let contents = std::fs::read(path)?;
+        let hash = Sha256::digest(&contents).to_vec();
+
+        let existing = self.by_hash.borrow().get(&hash).cloned();
+        let representative = match existing {
+            Some(existing) if existing != path && !has_dependencies(&contents) => existing,
+            Some(_) => path.to_path_buf(),
+            None => {
+                self.by_hash.borrow_mut().insert(hash, path.to_path_buf());
+                path.to_path_buf()
+            },
+        };
+
+        self.representatives.borrow_mut().insert(path.to_path_buf(), representative.clone());
+        Ok(representative)
+    }
+}
+
+/// Whether the source imports, re-exports or requires anything. Sources
+/// that don't parse count as having dependencies, so are never merged.
+fn has_dependencies(contents: &[u8]) -> bool {
+    let cm: Lrc<SourceMap> = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+    let fm = cm.new_source_file(FileName::Anon, String::from_utf8_lossy(contents).to_string());
+
+    let module = match parse_file_as_module(&fm, Syntax::Es(EsConfig::default()), EsVersion::latest(), None, &mut vec![]) {
+        Ok(module) => module,
+        Err(_) => return true,
+    };
+
+    let has_module_dependencies = module.body.iter().any(|item| matches!(
+        item,
+        ModuleItem::ModuleDecl(ModuleDecl::Import(_) | ModuleDecl::ExportAll(_))
+    ) || matches!(
+        item,
+        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) if export.src.is_some()
+    ));
+
+    let mut finder = DynamicImportFinder { found: false };
+    module.visit_with(&mut finder);
+
+    has_module_dependencies || finder.found || !interop::required_specifiers(&module).is_empty()
+}
+
+struct DynamicImportFinder {
+    found: bool,
+}
+
+impl Visit for DynamicImportFinder {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+
+        if matches!(call.callee, Callee::Import(_)) {
+            self.found = true;
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use anyhow::Error;
+
+use swc_common::{sync::Lrc, SourceMap, DUMMY_SP};
+use swc_ecma_ast::{CallExpr, Callee, Expr, ExprOrSpread, Ident, Lit, MemberExpr, MemberProp, Module, Str};
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+use crate::interop;
+
+/// Turns every `import("...")` of a module that isn't external into a
+/// static namespace import, resolved straight away, so the module ends up
+/// in the same bundle: `import("./a.js")` becomes `Promise.resolve(ns)`
+/// with `import * as ns from "./a.js"` at the top.
+///
+/// The imported module now runs with the rest of the bundle rather than
+/// when it's first asked for. Imports of computed specifiers are left as
+/// they are.
+pub fn inline_dynamic_imports(cm: &Lrc<SourceMap>, mut module: Module, externals: &[String]) -> Result<Module, Error> {
+    let mut inliner = DynamicImportInliner { externals, specifiers: vec![] };
+    module.visit_mut_with(&mut inliner);
+
+    if inliner.specifiers.is_empty() {
+        return Ok(module);
+    }
+
+    let mut src = String::new();
+    for (index, specifier) in inliner.specifiers.iter().enumerate() {
+        src.push_str(&format!("import * as __dynamic_import_{index} from {specifier:?};\n"));
+    }
+
+    let imports = interop::parse_generated(cm, "please-bundle:dynamic-imports", src)?;
+    module.body.splice(0..0, imports.body);
+    Ok(module)
+}
+
+struct DynamicImportInliner<'a> {
+    externals: &'a [String],
+    specifiers: Vec<String>,
+}
+
+impl VisitMut for DynamicImportInliner<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        expr.visit_mut_children_with(self);
+
+        let specifier = match expr {
+            Expr::Call(call) => match dynamic_import_specifier(call) {
+                Some(specifier) => specifier.value.to_string(),
+                None => return,
+            },
+            _ => return,
+        };
+
+        if self.externals.contains(&specifier) {
+            return;
+        }
+
+        let index = match self.specifiers.iter().position(|existing| *existing == specifier) {
+            Some(index) => index,
+            None => {
+                self.specifiers.push(specifier);
+                self.specifiers.len() - 1
+            },
+        };
+
+        *expr = Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+                span: DUMMY_SP,
+                obj: Box::new(Expr::Ident(Ident::new("Promise".into(), DUMMY_SP))),
+                prop: MemberProp::Ident(Ident::new("resolve".into(), DUMMY_SP)),
+            }))),
+            args: vec![ExprOrSpread {
+                spread: None,
+                expr: Box::new(Expr::Ident(Ident::new(format!("__dynamic_import_{index}").into(), DUMMY_SP))),
+            }],
+            type_args: None,
+        });
+    }
+}
+
+/// The literal specifiers of every `import()` left in `module`.
+pub fn dynamic_import_specifiers(module: &Module) -> Vec<String> {
+    let mut finder = DynamicImportFinder { specifiers: vec![] };
+    module.visit_with(&mut finder);
+    finder.specifiers
+}
+
+/// Points `import()`s of the specifiers in `renames` at their replacement.
+pub fn rename_dynamic_imports(module: &mut Module, renames: &HashMap<String, String>) {
+    module.visit_mut_with(&mut DynamicImportRenamer { renames });
+}
+
+fn dynamic_import_specifier(call: &CallExpr) -> Option<&Str> {
+    match (&call.callee, call.args.as_slice()) {
+        (Callee::Import(_), [arg]) => match &*arg.expr {
+            Expr::Lit(Lit::Str(specifier)) => Some(specifier),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+struct DynamicImportFinder {
+    specifiers: Vec<String>,
+}
+
+impl Visit for DynamicImportFinder {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+
+        if let Some(specifier) = dynamic_import_specifier(call) {
+            self.specifiers.push(specifier.value.to_string());
+        }
+    }
+}
+
+struct DynamicImportRenamer<'a> {
+    renames: &'a HashMap<String, String>,
+}
+
+impl VisitMut for DynamicImportRenamer<'_> {
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+
+        let renamed = match dynamic_import_specifier(call).and_then(|specifier| self.renames.get(&*specifier.value)) {
+            Some(renamed) => renamed.clone(),
+            None => return,
+        };
+
+        *call.args[0].expr = Expr::Lit(Lit::Str(renamed.into()));
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+
+use anyhow::{Error, bail};
+
+use swc_common::{BytePos, DUMMY_SP};
+use swc_ecma_ast::{
+    AssignExpr, AssignOp, BinExpr, BinaryOp, BindingIdent, BlockStmt, CallExpr, Callee, ComputedPropName, Decl,
+    ExportDecl, Expr, ExprOrSpread, ExprStmt, FnExpr, Function, Ident, Lit, MemberExpr, MemberProp, Module, ModuleDecl,
+    ModuleItem, Number, ObjectLit, Param, ParenExpr, Pat, PatOrExpr, Stmt, Str, TsEnumDecl, TsEnumMemberId,
+    UnaryExpr, UnaryOp, VarDecl, VarDeclKind, VarDeclarator,
+};
+use swc_ecma_visit::{VisitMut, VisitMutWith};
+
+/// Compiles enums into the objects TypeScript makes of them:
+///
+/// ```js
+/// var Colour;
+/// (function (Colour) {
+///     Colour[Colour["Red"] = 0] = "Red";
+/// })(Colour || (Colour = {}));
+/// ```
+///
+/// Const enums are compiled the same way, so importing one from another
+/// module still works, and their members are inlined wherever they're used
+/// in the module declaring them. The const enums are handed back so they
+/// can be inlined in the modules importing them too, see [inline_imported].
+pub fn lower_enums(module: &mut Module) -> Result<ConstEnums, Error> {
+    let mut lowerer = EnumLowerer { const_values: HashMap::new(), const_declarations: HashMap::new(), error: None };
+    module.visit_mut_with(&mut lowerer);
+
+    if let Some(error) = lowerer.error {
+        return Err(error);
+    }
+
+    if !lowerer.const_values.is_empty() {
+        module.visit_mut_with(&mut ConstEnumInliner { values: &lowerer.const_values });
+    }
+
+    let mut const_enums = ConstEnums::default();
+    for (name, positions) in lowerer.const_declarations {
+        for position in positions {
+            const_enums.0.insert(position, lowerer.const_values[&name].clone());
+        }
+    }
+    Ok(const_enums)
+}
+
+/// The members of const enums, by where each declaration of one starts.
+#[derive(Default)]
+pub struct ConstEnums(HashMap<BytePos, HashMap<String, Value>>);
+
+impl ConstEnums {
+    pub fn extend(&mut self, other: ConstEnums) {
+        self.0.extend(other.0);
+    }
+}
+
+/// Inlines the members of const enums declared in other modules, once the
+/// bundler has put them and the modules importing them together. An
+/// enum's declaration keeps the position of the one it was lowered from
+/// whatever the bundler renames it to, which is how they're found.
+pub fn inline_imported(module: &mut Module, const_enums: &ConstEnums) {
+    let mut values: HashMap<String, HashMap<String, Value>> = HashMap::new();
+    for item in &module.body {
+        let decl = match item {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Var(decl))) => decl,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl: Decl::Var(decl), .. })) => decl,
+            _ => continue,
+        };
+        for declarator in &decl.decls {
+            if let Pat::Ident(BindingIdent { id, .. }) = &declarator.name {
+                if let Some(members) = const_enums.0.get(&id.span.lo).filter(|_| !id.span.is_dummy()) {
+                    values.entry(id.sym.to_string()).or_default().extend(members.clone());
+                }
+            }
+        }
+    }
+
+    if !values.is_empty() {
+        module.visit_mut_with(&mut ConstEnumInliner { values: &values });
+    }
+}
+
+#[derive(Clone)]
+enum Value {
+    Number(f64),
+    String(String),
+}
+
+struct EnumLowerer {
+    /// Member values of every const enum, by enum name.
+    const_values: HashMap<String, HashMap<String, Value>>,
+    /// Where each declaration of every const enum starts, by enum name.
+    const_declarations: HashMap<String, Vec<BytePos>>,
+    error: Option<Error>,
+}
+
+impl EnumLowerer {
+    fn lower(&mut self, enum_decl: &mut TsEnumDecl) -> Result<Vec<Stmt>, Error> {
+        let name = enum_decl.id.sym.to_string();
+        let mut values = HashMap::new();
+        let mut statements = vec![];
+        let mut next = Some(0.0);
+
+        for member in &mut enum_decl.members {
+            let member_name = match &member.id {
+                TsEnumMemberId::Ident(ident) => ident.sym.to_string(),
+                TsEnumMemberId::Str(s) => s.value.to_string(),
+            };
+
+            let value = match &member.init {
+                Some(init) => evaluate(init, &name, &values),
+                None => match next {
+                    Some(next) => Some(Value::Number(next)),
+                    None => bail!("enum member {name}.{member_name} needs an initializer"),
+                },
+            };
+
+            next = match &value {
+                Some(Value::Number(number)) => Some(number + 1.0),
+                _ => None,
+            };
+
+            let init = match &value {
+                Some(value) => value_expr(value),
+                None => {
+                    let mut init = *member.init.take().unwrap();
+                    init.visit_mut_with(&mut MemberQualifier { enum_name: &name, members: &values });
+                    init
+                },
+            };
+
+            // Numeric members map back from their value to their name too.
+            let assignment = assign(member_expr(&name, string(&member_name)), init);
+            let statement = match value {
+                Some(Value::String(_)) => assignment,
+                _ => assign(member_expr(&name, assignment), string(&member_name)),
+            };
+            statements.push(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(statement) }));
+
+            if let Some(value) = value {
+                values.insert(member_name, value);
+            }
+        }
+
+        if enum_decl.is_const {
+            self.const_values.entry(name.clone()).or_default().extend(values);
+            self.const_declarations.entry(name.clone()).or_default().push(enum_decl.id.span.lo);
+        }
+
+        Ok(vec![
+            Stmt::Decl(var_decl(&enum_decl.id)),
+            Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(iife(&enum_decl.id, statements)) }),
+        ])
+    }
+
+    fn lower_or_note(&mut self, enum_decl: &mut TsEnumDecl) -> Vec<Stmt> {
+        match self.lower(enum_decl) {
+            Ok(statements) => statements,
+            Err(why) => {
+                self.error.get_or_insert(why);
+                vec![]
+            },
+        }
+    }
+}
+
+impl VisitMut for EnumLowerer {
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.visit_mut_children_with(self);
+
+        let mut lowered = Vec::with_capacity(items.len());
+        for item in items.drain(..) {
+            match item {
+                ModuleItem::Stmt(Stmt::Decl(Decl::TsEnum(mut enum_decl))) if !enum_decl.declare => {
+                    lowered.extend(self.lower_or_note(&mut enum_decl).into_iter().map(ModuleItem::Stmt));
+                },
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl: Decl::TsEnum(mut enum_decl), span }))
+                    if !enum_decl.declare =>
+                {
+                    let mut statements = self.lower_or_note(&mut enum_decl).into_iter();
+                    if let Some(Stmt::Decl(decl)) = statements.next() {
+                        lowered.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { span, decl })));
+                    }
+                    lowered.extend(statements.map(ModuleItem::Stmt));
+                },
+                item => lowered.push(item),
+            }
+        }
+        *items = lowered;
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.visit_mut_children_with(self);
+
+        let mut lowered = Vec::with_capacity(stmts.len());
+        for stmt in stmts.drain(..) {
+            match stmt {
+                Stmt::Decl(Decl::TsEnum(mut enum_decl)) if !enum_decl.declare => {
+                    lowered.extend(self.lower_or_note(&mut enum_decl));
+                },
+                stmt => lowered.push(stmt),
+            }
+        }
+        *stmts = lowered;
+    }
+}
+
+/// The value of a member's initializer when it's a constant, which may
+/// refer to earlier members.
+fn evaluate(expr: &Expr, enum_name: &str, values: &HashMap<String, Value>) -> Option<Value> {
+    match expr {
+        Expr::Lit(Lit::Num(number)) => Some(Value::Number(number.value)),
+        Expr::Lit(Lit::Str(s)) => Some(Value::String(s.value.to_string())),
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => {
+            tpl.quasis.first().and_then(|quasi| quasi.cooked.as_ref()).map(|cooked| Value::String(cooked.to_string()))
+        },
+        Expr::Paren(paren) => evaluate(&paren.expr, enum_name, values),
+        Expr::Ident(ident) => values.get(&*ident.sym).cloned(),
+        Expr::Member(MemberExpr { obj, prop, .. }) => match (&**obj, prop) {
+            (Expr::Ident(obj), MemberProp::Ident(prop)) if &*obj.sym == enum_name => values.get(&*prop.sym).cloned(),
+            _ => None,
+        },
+        Expr::Unary(unary) => match (unary.op, evaluate(&unary.arg, enum_name, values)?) {
+            (UnaryOp::Minus, Value::Number(number)) => Some(Value::Number(-number)),
+            (UnaryOp::Plus, Value::Number(number)) => Some(Value::Number(number)),
+            (UnaryOp::Tilde, Value::Number(number)) => Some(Value::Number(!to_int32(number) as f64)),
+            _ => None,
+        },
+        Expr::Bin(bin) => {
+            let left = evaluate(&bin.left, enum_name, values)?;
+            let right = evaluate(&bin.right, enum_name, values)?;
+            match (left, right) {
+                (Value::Number(left), Value::Number(right)) => binary(bin.op, left, right).map(Value::Number),
+                (Value::String(left), Value::String(right)) if bin.op == BinaryOp::Add => {
+                    Some(Value::String(left + &right))
+                },
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+fn binary(op: BinaryOp, left: f64, right: f64) -> Option<f64> {
+    Some(match op {
+        BinaryOp::Add => left + right,
+        BinaryOp::Sub => left - right,
+        BinaryOp::Mul => left * right,
+        BinaryOp::Div => left / right,
+        BinaryOp::Mod => left % right,
+        BinaryOp::Exp => left.powf(right),
+        BinaryOp::BitOr => (to_int32(left) | to_int32(right)) as f64,
+        BinaryOp::BitAnd => (to_int32(left) & to_int32(right)) as f64,
+        BinaryOp::BitXor => (to_int32(left) ^ to_int32(right)) as f64,
+        BinaryOp::LShift => to_int32(left).wrapping_shl(to_int32(right) as u32 & 31) as f64,
+        BinaryOp::RShift => to_int32(left).wrapping_shr(to_int32(right) as u32 & 31) as f64,
+        BinaryOp::ZeroFillRShift => (to_int32(left) as u32).wrapping_shr(to_int32(right) as u32 & 31) as f64,
+        _ => return None,
+    })
+}
+
+/// JavaScript's ToInt32, as used by the bitwise operators.
+fn to_int32(number: f64) -> i32 {
+    if !number.is_finite() {
+        return 0;
+    }
+    number.trunc().rem_euclid(4294967296.0) as u32 as i32
+}
+
+/// Points references to earlier members in an initializer that isn't a
+/// constant at the enum: `b = a + f()` becomes `b = E.a + f()`.
+struct MemberQualifier<'a> {
+    enum_name: &'a str,
+    members: &'a HashMap<String, Value>,
+}
+
+impl VisitMut for MemberQualifier<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Ident(ident) if self.members.contains_key(&*ident.sym) => {
+                *expr = member_expr(self.enum_name, string(&ident.sym));
+            },
+            _ => expr.visit_mut_children_with(self),
+        }
+    }
+}
+
+/// Replaces `E.a` and `E["a"]` of const enums with the member's value.
+struct ConstEnumInliner<'a> {
+    values: &'a HashMap<String, HashMap<String, Value>>,
+}
+
+impl VisitMut for ConstEnumInliner<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        let value = match expr {
+            Expr::Member(MemberExpr { obj, prop, .. }) => match &**obj {
+                Expr::Ident(obj) => {
+                    let member = match prop {
+                        MemberProp::Ident(prop) => Some(prop.sym.to_string()),
+                        MemberProp::Computed(ComputedPropName { expr, .. }) => match &**expr {
+                            Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+                            _ => None,
+                        },
+                        MemberProp::PrivateName(_) => None,
+                    };
+                    member.and_then(|member| self.values.get(&*obj.sym)?.get(&member).cloned())
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match value {
+            Some(value) => *expr = value_expr(&value),
+            None => expr.visit_mut_children_with(self),
+        }
+    }
+
+    fn visit_mut_pat_or_expr(&mut self, target: &mut PatOrExpr) {
+        // The members assigned in the enum's own compiled body stay as they are.
+        if let PatOrExpr::Expr(expr) = target {
+            if let Expr::Member(member) = &mut **expr {
+                member.prop.visit_mut_with(self);
+                return;
+            }
+        }
+        target.visit_mut_children_with(self);
+    }
+}
+
+fn value_expr(value: &Value) -> Expr {
+    match value {
+        Value::String(s) => string(s),
+        Value::Number(number) if number.is_nan() => Expr::Ident(Ident::new("NaN".into(), DUMMY_SP)),
+        Value::Number(number) if number.is_sign_negative() && *number != 0.0 => Expr::Unary(UnaryExpr {
+            span: DUMMY_SP,
+            op: UnaryOp::Minus,
+            arg: Box::new(value_expr(&Value::Number(-number))),
+        }),
+        Value::Number(number) if number.is_infinite() => Expr::Ident(Ident::new("Infinity".into(), DUMMY_SP)),
+        Value::Number(number) => Expr::Lit(Lit::Num(Number { span: DUMMY_SP, value: *number, raw: None })),
+    }
+}
+
+fn string(value: &str) -> Expr {
+    Expr::Lit(Lit::Str(Str::from(value.to_string())))
+}
+
+fn ident(name: &Ident) -> Ident {
+    Ident::new(name.sym.clone(), DUMMY_SP)
+}
+
+/// `E[key]`.
+fn member_expr(enum_name: &str, key: Expr) -> Expr {
+    Expr::Member(MemberExpr {
+        span: DUMMY_SP,
+        obj: Box::new(Expr::Ident(Ident::new(enum_name.into(), DUMMY_SP))),
+        prop: MemberProp::Computed(ComputedPropName { span: DUMMY_SP, expr: Box::new(key) }),
+    })
+}
+
+fn assign(target: Expr, value: Expr) -> Expr {
+    Expr::Assign(AssignExpr {
+        span: DUMMY_SP,
+        op: AssignOp::Assign,
+        left: PatOrExpr::Expr(Box::new(target)),
+        right: Box::new(value),
+    })
+}
+
+/// `var E;`
+fn var_decl(name: &Ident) -> Decl {
+    Decl::Var(Box::new(VarDecl {
+        span: DUMMY_SP,
+        kind: VarDeclKind::Var,
+        declare: false,
+        decls: vec![VarDeclarator {
+            span: DUMMY_SP,
+            name: Pat::Ident(BindingIdent { id: name.clone(), type_ann: None }),
+            init: None,
+            definite: false,
+        }],
+    }))
+}
+
+/// `(function (E) { ... })(E || (E = {}))`, which adds to the enum if an
+/// earlier declaration of it already made the object.
+fn iife(name: &Ident, stmts: Vec<Stmt>) -> Expr {
+    let function = Function {
+        params: vec![Param {
+            span: DUMMY_SP,
+            decorators: vec![],
+            pat: Pat::Ident(BindingIdent { id: ident(name), type_ann: None }),
+        }],
+        decorators: vec![],
+        span: DUMMY_SP,
+        body: Some(BlockStmt { span: DUMMY_SP, stmts }),
+        is_generator: false,
+        is_async: false,
+        type_params: None,
+        return_type: None,
+    };
+
+    let object = Expr::Bin(BinExpr {
+        span: DUMMY_SP,
+        op: BinaryOp::LogicalOr,
+        left: Box::new(Expr::Ident(ident(name))),
+        right: Box::new(Expr::Paren(ParenExpr {
+            span: DUMMY_SP,
+            expr: Box::new(assign(Expr::Ident(ident(name)), Expr::Object(ObjectLit { span: DUMMY_SP, props: vec![] }))),
+        })),
+    });
+
+    Expr::Call(CallExpr {
+        span: DUMMY_SP,
+        callee: Callee::Expr(Box::new(Expr::Paren(ParenExpr {
+            span: DUMMY_SP,
+            expr: Box::new(Expr::Fn(FnExpr { ident: None, function: Box::new(function) })),
+        }))),
+        args: vec![ExprOrSpread { spread: None, expr: Box::new(object) }],
+        type_args: None,
+    })
+}
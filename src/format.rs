@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use anyhow::Error;
+
+use swc_common::{comments::SingleThreadedComments, Globals, Mark, GLOBALS};
+use swc_ecma_ast::{Expr, Lit, Module, ModuleDecl, ModuleItem, Stmt};
+use swc_ecma_transforms_base::{
+    feature::FeatureFlag,
+    fixer::fixer,
+    helpers::{inject_helpers, Helpers, HELPERS},
+    hygiene::hygiene,
+    resolver,
+};
+use swc_ecma_transforms_module::{
+    amd::{self, amd},
+    common_js::common_js,
+    system_js::{self, system_js},
+};
+use swc_ecma_visit::{FoldWith, VisitMut, VisitMutWith};
+
+use crate::dynamic;
+use crate::interop;
+
+/// The module format the bundle is emitted in.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// An ES module, exactly as the bundler produced it.
+    Esm,
+    /// A `System.register` module for SystemJS and import map based loaders.
+    System,
+    /// A `define()` module for RequireJS and other AMD loaders.
+    Amd,
+    /// A CommonJS module, for runtimes that `require` their entry point.
+    Cjs,
+}
+
+impl Format {
+    /// The name `[format]` is replaced with in entry names.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Esm => "esm",
+            Format::System => "system",
+            Format::Amd => "amd",
+            Format::Cjs => "cjs",
+        }
+    }
+}
+
+pub struct Options {
+    /// Id passed as the first argument to `define()`, anonymous when unset.
+    pub amd_id: Option<String>,
+    /// AMD module ids to depend on in place of external specifiers.
+    pub amd_dependencies: HashMap<String, String>,
+    /// Whether System, AMD and CommonJS modules are put in strict mode. ES
+    /// modules always are.
+    pub strict: bool,
+    /// Whether ES modules make their own `require` when they need one, for
+    /// Node and the runtimes that follow it.
+    pub create_require: bool,
+    /// Whether ES modules work out `__dirname` and `__filename` from
+    /// `import.meta.url` when they need them.
+    pub import_meta_dirname: bool,
+}
+
+/// Converts the ES module produced by the bundler into `format`.
+pub fn convert(globals: &Globals, mut module: Module, format: Format, options: &Options) -> Result<Module, Error> {
+    // Once the whole bundle is strict, the directives modules brought with
+    // them say nothing, wherever they ended up.
+    if format == Format::Esm || options.strict {
+        module.visit_mut_with(&mut UseStrictRemover);
+    }
+
+    if format == Format::Esm {
+        if options.create_require || options.import_meta_dirname {
+            module = interop::inject_node_globals(globals, module, options.create_require, options.import_meta_dirname)?;
+        }
+        return Ok(module);
+    }
+
+    if format == Format::Amd {
+        rename_sources(&mut module, &options.amd_dependencies);
+    }
+
+    // The module transforms lean on swc's helpers for things like `import()`,
+    // which are inlined into the output.
+    GLOBALS.set(globals, || HELPERS.set(&Helpers::new(false), || {
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+
+        let module = module.fold_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        let module = match format {
+            Format::Esm => module,
+            Format::System => module.fold_with(&mut system_js(unresolved_mark, system_js::Config::default())),
+            Format::Amd => module.fold_with(&mut amd(
+                unresolved_mark,
+                amd::Config {
+                    module_id: options.amd_id.clone(),
+                    config: swc_ecma_transforms_module::util::Config {
+                        strict_mode: options.strict,
+                        ..Default::default()
+                    },
+                },
+                FeatureFlag::empty(),
+                None::<SingleThreadedComments>,
+            )),
+            Format::Cjs => module.fold_with(&mut common_js(
+                unresolved_mark,
+                swc_ecma_transforms_module::util::Config {
+                    strict_mode: options.strict,
+                    ..Default::default()
+                },
+                FeatureFlag::empty(),
+                None::<SingleThreadedComments>,
+            )),
+        };
+
+        let mut module = module
+            .fold_with(&mut inject_helpers(unresolved_mark))
+            .fold_with(&mut hygiene())
+            .fold_with(&mut fixer(None));
+
+        // The System transform always makes its factory strict.
+        if format == Format::System && !options.strict {
+            drop_factory_use_strict(&mut module);
+        }
+
+        Ok(module)
+    }))
+}
+
+/// Removes the directive the `System.register` factory function starts
+/// with.
+fn drop_factory_use_strict(module: &mut Module) {
+    for item in &mut module.body {
+        let call = match item {
+            ModuleItem::Stmt(Stmt::Expr(stmt)) => match &mut *stmt.expr {
+                Expr::Call(call) => call,
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        for arg in &mut call.args {
+            if let Expr::Fn(factory) = &mut *arg.expr {
+                if let Some(body) = &mut factory.function.body {
+                    body.stmts.retain(|stmt| !is_use_strict(stmt));
+                }
+            }
+        }
+    }
+}
+
+/// Removes every `"use strict"` directive.
+struct UseStrictRemover;
+
+impl VisitMut for UseStrictRemover {
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.retain(|item| !matches!(item, ModuleItem::Stmt(stmt) if is_use_strict(stmt)));
+        items.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.retain(|stmt| !is_use_strict(stmt));
+        stmts.visit_mut_children_with(self);
+    }
+}
+
+fn is_use_strict(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Expr(stmt) if matches!(&*stmt.expr, Expr::Lit(Lit::Str(s)) if s.value == *"use strict"))
+}
+
+/// Points imports, re-exports and `import()`s of the external specifiers in
+/// `renames` at their replacement.
+fn rename_sources(module: &mut Module, renames: &HashMap<String, String>) {
+    for item in &mut module.body {
+        let src = match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => &mut import.src,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) => match &mut export.src {
+                Some(src) => src,
+                None => continue,
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export)) => &mut export.src,
+            _ => continue,
+        };
+
+        if let Some(renamed) = renames.get(&*src.value) {
+            src.value = renamed.as_str().into();
+            src.raw = None;
+        }
+    }
+
+    dynamic::rename_dynamic_imports(module, renames);
+}
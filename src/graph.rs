@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use swc_common::{sync::Lock, FileName};
+
+/// What to do when a check over the module graph finds a problem.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    Ignore,
+    Warn,
+    Error,
+}
+
+/// Import edges between modules, recorded by the resolver as the bundler
+/// walks the graph.
+#[derive(Default)]
+pub struct ModuleGraph {
+    edges: Lock<BTreeMap<String, BTreeSet<String>>>,
+    resolutions: Lock<BTreeMap<(String, String), String>>,
+}
+
+impl ModuleGraph {
+    pub fn add_edge(&self, from: &FileName, specifier: &str, to: &FileName) {
+        self.edges.borrow_mut()
+            .entry(from.to_string())
+            .or_default()
+            .insert(to.to_string());
+
+        // Each entry merges identical modules on its own, so the same import
+        // can resolve to different copies. The first in name order is kept
+        // whichever entry got there first.
+        self.resolutions.borrow_mut()
+            .entry((from.to_string(), specifier.to_string()))
+            .and_modify(|existing| if to.to_string() < *existing { *existing = to.to_string() })
+            .or_insert_with(|| to.to_string());
+    }
+
+    /// What `specifier` resolved to when imported from `from`.
+    pub fn resolution(&self, from: &str, specifier: &str) -> Option<String> {
+        self.resolutions.borrow()
+            .get(&(from.to_string(), specifier.to_string()))
+            .cloned()
+    }
+
+    /// The shortest chain of imports from `from` to `to`, both included.
+    pub fn chain(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let edges = self.edges.borrow();
+
+        let mut previous: BTreeMap<&str, &str> = BTreeMap::new();
+        let mut queue = std::collections::VecDeque::from([from]);
+
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut chain = vec![to.to_string()];
+                let mut current = to;
+                while current != from {
+                    current = previous[current];
+                    chain.push(current.to_string());
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+
+            for target in edges.get(node).into_iter().flatten() {
+                if target != from && !previous.contains_key(target.as_str()) {
+                    previous.insert(target, node);
+                    queue.push_back(target);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// One chain of modules for every strongly connected component of the
+    /// graph, each starting and ending with the same module.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let edges = self.edges.borrow();
+
+        strongly_connected_components(&edges).into_iter()
+            .filter(|component| {
+                component.len() > 1 || edges.get(&component[0]).is_some_and(|to| to.contains(&component[0]))
+            })
+            .filter_map(|component| cycle_through(&edges, &component))
+            .collect()
+    }
+}
+
+/// Tarjan's algorithm, with modules visited in sorted order so the
+/// components come out the same every run.
+fn strongly_connected_components(edges: &BTreeMap<String, BTreeSet<String>>) -> Vec<Vec<String>> {
+    struct State<'a> {
+        edges: &'a BTreeMap<String, BTreeSet<String>>,
+        index: usize,
+        indices: BTreeMap<&'a str, usize>,
+        lowlinks: BTreeMap<&'a str, usize>,
+        stack: Vec<&'a str>,
+        on_stack: BTreeSet<&'a str>,
+        components: Vec<Vec<String>>,
+    }
+
+    fn visit<'a>(state: &mut State<'a>, node: &'a str) {
+        state.indices.insert(node, state.index);
+        state.lowlinks.insert(node, state.index);
+        state.index += 1;
+        state.stack.push(node);
+        state.on_stack.insert(node);
+
+        if let Some(targets) = state.edges.get(node) {
+            for target in targets {
+                if !state.indices.contains_key(target.as_str()) {
+                    visit(state, target);
+                    let lowlink = state.lowlinks[node].min(state.lowlinks[target.as_str()]);
+                    state.lowlinks.insert(node, lowlink);
+                } else if state.on_stack.contains(target.as_str()) {
+                    let lowlink = state.lowlinks[node].min(state.indices[target.as_str()]);
+                    state.lowlinks.insert(node, lowlink);
+                }
+            }
+        }
+
+        if state.lowlinks[node] == state.indices[node] {
+            let mut component = vec![];
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(member);
+                component.push(member.to_string());
+                if member == node {
+                    break;
+                }
+            }
+            component.sort();
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        edges,
+        index: 0,
+        indices: BTreeMap::new(),
+        lowlinks: BTreeMap::new(),
+        stack: vec![],
+        on_stack: BTreeSet::new(),
+        components: vec![],
+    };
+
+    for node in edges.keys() {
+        if !state.indices.contains_key(node.as_str()) {
+            visit(&mut state, node);
+        }
+    }
+
+    state.components.sort();
+    state.components
+}
+
+/// The shortest chain from the first member of `component` back to itself,
+/// staying inside the component.
+fn cycle_through(edges: &BTreeMap<String, BTreeSet<String>>, component: &[String]) -> Option<Vec<String>> {
+    let start = &component[0];
+    let members: BTreeSet<&String> = component.iter().collect();
+
+    let mut previous: BTreeMap<&String, &String> = BTreeMap::new();
+    let mut queue = std::collections::VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        for target in edges.get(node).into_iter().flatten() {
+            if !members.contains(target) {
+                continue;
+            }
+
+            if target == start {
+                let mut chain = vec![start.clone()];
+                let mut current = node;
+                while current != start {
+                    chain.push(current.clone());
+                    current = previous[current];
+                }
+                chain.push(start.clone());
+                let len = chain.len();
+                chain[1..len - 1].reverse();
+                return Some(chain);
+            }
+
+            if !previous.contains_key(target) {
+                previous.insert(target, node);
+                queue.push_back(target);
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,155 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Error, bail};
+
+use serde::{Deserialize, Serialize};
+
+use swc_ecma_ast::{Module, ModuleDecl, ModuleItem};
+
+use crate::{dynamic, paths};
+
+/// An import map as understood by browsers. Addresses and scopes that are
+/// relative or absolute paths point at local files, resolved against the
+/// directory the map lives in; anything else (`https://...`) is left for the
+/// host page to resolve at runtime so the specifier is treated as external.
+#[derive(Deserialize, Default, Clone)]
+pub struct ImportMap {
+    #[serde(default)]
+    imports: HashMap<String, String>,
+
+    #[serde(default)]
+    scopes: HashMap<String, HashMap<String, String>>,
+
+    #[serde(skip)]
+    base_dir: PathBuf,
+}
+
+pub enum Address {
+    Local(PathBuf),
+    External,
+}
+
+impl ImportMap {
+    pub fn load(path: &Path) -> Result<ImportMap, Error> {
+        let mut file = File::open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let mut import_map: ImportMap = serde_json::from_str(&contents)?;
+        // Scopes are matched against the absolute paths of importers.
+        import_map.base_dir = match path.parent() {
+            None => bail!("no directory for import map {path:?}"),
+            Some(dir) if dir != Path::new("") => paths::canonicalize(dir)?,
+            Some(_) => paths::current_dir()?,
+        };
+
+        Ok(import_map)
+    }
+
+    /// Exact specifiers mapped to remote addresses. Prefix mappings can't be
+    /// listed up front, so remote prefixes have to be made external by hand.
+    pub fn externals(&self) -> Vec<String> {
+        let mut externals: Vec<String> = std::iter::once(&self.imports)
+            .chain(self.scopes.values())
+            .flat_map(|imports| imports.iter())
+            .filter(|(specifier, address)| !specifier.ends_with('/') && !is_local(address))
+            .map(|(specifier, _)| specifier.clone())
+            .collect();
+
+        externals.sort();
+        externals.dedup();
+        externals
+    }
+
+    /// Resolves `specifier` imported from `base` using the most specific
+    /// scope containing `base` first, then the top level imports.
+    pub fn resolve(&self, base: &Path, specifier: &str) -> Option<Address> {
+        let mut scopes: Vec<(PathBuf, &HashMap<String, String>)> = self.scopes.iter()
+            .filter(|(scope, _)| is_local(scope))
+            .map(|(scope, imports)| (self.base_dir.join(scope.trim_start_matches('/')), imports))
+            .filter(|(scope, _)| base.starts_with(scope))
+            .collect();
+        scopes.sort_by_key(|(scope, _)| std::cmp::Reverse(scope.components().count()));
+
+        scopes.into_iter()
+            .map(|(_, imports)| imports)
+            .chain(std::iter::once(&self.imports))
+            .find_map(|imports| self.resolve_in(imports, specifier))
+    }
+
+    /// The remote address a top level entry gives `specifier`, if any.
+    pub fn remote_address(&self, specifier: &str) -> Option<&str> {
+        self.imports.get(specifier)
+            .filter(|address| !is_local(address))
+            .map(|address| address.as_str())
+    }
+
+    fn resolve_in(&self, imports: &HashMap<String, String>, specifier: &str) -> Option<Address> {
+        let address = match imports.get(specifier) {
+            Some(address) => address.clone(),
+            None => {
+                let (prefix, address) = imports.iter()
+                    .filter(|(prefix, _)| prefix.ends_with('/') && specifier.starts_with(prefix.as_str()))
+                    .max_by_key(|(prefix, _)| prefix.len())?;
+
+                format!("{address}{}", &specifier[prefix.len()..])
+            },
+        };
+
+        if is_local(&address) {
+            Some(Address::Local(self.base_dir.join(address.trim_start_matches('/'))))
+        } else {
+            Some(Address::External)
+        }
+    }
+}
+
+pub fn is_local(address: &str) -> bool {
+    address.starts_with("./") || address.starts_with("../") || address.starts_with('/')
+}
+
+#[derive(Serialize)]
+struct GeneratedImportMap {
+    imports: BTreeMap<String, String>,
+}
+
+/// An import map pointing every external the bundles still import,
+/// statically or with `import()`, at
+/// `url_pattern`, with `[name]` replaced by the specifier. Specifiers that
+/// already have a remote address in `input` keep it.
+pub fn externals_import_map(
+    modules: &[&Module],
+    externals: &[String],
+    url_pattern: &str,
+    input: Option<&ImportMap>,
+) -> Result<Vec<u8>, Error> {
+    let imports = modules.iter()
+        .flat_map(|module| imported_sources(module))
+        .filter(|specifier| externals.contains(specifier))
+        .map(|specifier| {
+            let address = match input.and_then(|import_map| import_map.remote_address(&specifier)) {
+                Some(address) => address.to_string(),
+                None => url_pattern.replace("[name]", &specifier),
+            };
+            (specifier, address)
+        })
+        .collect();
+
+    Ok(serde_json::to_vec_pretty(&GeneratedImportMap { imports })?)
+}
+
+fn imported_sources(module: &Module) -> Vec<String> {
+    module.body.iter()
+        .filter_map(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => Some(&import.src),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) => export.src.as_ref(),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export)) => Some(&export.src),
+            _ => None,
+        })
+        .map(|src| src.value.to_string())
+        .chain(dynamic::dynamic_import_specifiers(module))
+        .collect()
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Error, anyhow, bail};
+
+use serde_json::Value;
+
+use sha2::{Digest, Sha256, Sha512};
+
+/// Where the tarball npm would have installed `name@version` from is kept
+/// in `dir`, named as `npm pack` names it: `@scope/name` becomes
+/// `scope-name-1.0.0.tgz`.
+pub fn tarball_path(dir: &Path, name: &str, version: &str) -> PathBuf {
+    dir.join(format!("{}-{version}.tgz", name.trim_start_matches('@').replace('/', "-")))
+}
+
+/// How the package in `package_dir` differs from the tarball it was
+/// installed from, checking the tarball against the lockfile's `integrity`
+/// first and then every file in it against what's on disk. Files that the
+/// tarball doesn't have come up too, other than installed dependencies.
+pub fn verify(id: &str, package_dir: &Path, tarball: &Path, integrity: &str) -> Result<Vec<String>, Error> {
+    if !tarball.exists() {
+        return Ok(vec![format!("{id} has no tarball at {} to verify it against", tarball.display())]);
+    }
+
+    let contents = std::fs::read(tarball)?;
+    let actual = match integrity.split_once('-') {
+        Some(("sha512", _)) => format!("sha512-{}", base64::encode(Sha512::digest(&contents))),
+        Some(("sha256", _)) => format!("sha256-{}", base64::encode(Sha256::digest(&contents))),
+        _ => bail!("{id} has integrity {integrity} in the lockfile, only sha512 and sha256 can be verified"),
+    };
+    if actual != integrity {
+        return Ok(vec![format!("{id}'s tarball has integrity {actual} but the lockfile has {integrity}")]);
+    }
+
+    let extracted = extract(tarball)?;
+    let result = compare(id, package_dir, &extracted);
+    std::fs::remove_dir_all(&extracted)?;
+    result
+}
+
+/// Unpacks `tarball` into a directory of its own with `tar`, returning
+/// where to find it.
+fn extract(tarball: &Path) -> Result<PathBuf, Error> {
+    static EXTRACTIONS: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = std::env::temp_dir().join(format!("please-bundle-{}-{}", std::process::id(), EXTRACTIONS.fetch_add(1, Ordering::Relaxed)));
+    std::fs::create_dir_all(&dir)?;
+
+    let output = Command::new("tar")
+        .arg("-xzf")
+        .arg(tarball)
+        .arg("-C")
+        .arg(&dir)
+        .output()
+        .map_err(|why| anyhow!("failed to run tar for {}: {why}", tarball.display()))?;
+
+    if !output.status.success() {
+        std::fs::remove_dir_all(&dir)?;
+        bail!("failed to unpack {}: {}", tarball.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(dir)
+}
+
+fn compare(id: &str, package_dir: &Path, extracted: &Path) -> Result<Vec<String>, Error> {
+    // npm packs everything under `package/`, but some tarballs choose
+    // another name for the one directory at the top.
+    let tops: Vec<PathBuf> = std::fs::read_dir(extracted)?.map(|entry| Ok(entry?.path())).collect::<Result<_, Error>>()?;
+    let root = match tops.as_slice() {
+        [top] if top.is_dir() => top.clone(),
+        _ => extracted.to_path_buf(),
+    };
+
+    let packed = files(&root, false)?;
+    let installed = files(package_dir, true)?;
+
+    let mut differences = vec![];
+    for file in &packed {
+        if !installed.contains(file) {
+            differences.push(format!("{id} is missing {} from its tarball", file.display()));
+        } else if !same_contents(file, &std::fs::read(root.join(file))?, &std::fs::read(package_dir.join(file))?) {
+            differences.push(format!("{id} has {} that doesn't match its tarball", file.display()));
+        }
+    }
+    for file in installed.difference(&packed) {
+        differences.push(format!("{id} has {} that isn't in its tarball", file.display()));
+    }
+
+    Ok(differences)
+}
+
+/// Whether `installed` is what was `packed`. Older npms write what they
+/// installed a package from into its `package.json` under keys starting
+/// with `_`, and the rest may be laid out afresh, so manifests are compared
+/// as JSON without those keys.
+fn same_contents(file: &Path, packed: &[u8], installed: &[u8]) -> bool {
+    if file != Path::new("package.json") {
+        return packed == installed;
+    }
+
+    let manifest = |contents: &[u8]| match serde_json::from_slice::<Value>(contents) {
+        Ok(Value::Object(mut fields)) => {
+            fields.retain(|key, _| !key.starts_with('_'));
+            Some(Value::Object(fields))
+        },
+        _ => None,
+    };
+    match (manifest(packed), manifest(installed)) {
+        (Some(packed), Some(installed)) => packed == installed,
+        _ => packed == installed,
+    }
+}
+
+fn files(dir: &Path, skip_dependencies: bool) -> Result<BTreeSet<PathBuf>, Error> {
+    let mut files = BTreeSet::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(next) = dirs.pop() {
+        for entry in std::fs::read_dir(&next)? {
+            let path = entry?.path();
+            if skip_dependencies && path.file_name().is_some_and(|name| name == "node_modules") {
+                continue;
+            }
+            if path.is_dir() {
+                dirs.push(path);
+            } else if let Ok(file) = path.strip_prefix(dir) {
+                files.insert(file.to_path_buf());
+            }
+        }
+    }
+    Ok(files)
+}
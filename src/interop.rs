@@ -0,0 +1,751 @@
+use std::path::Path;
+
+use anyhow::{Error, anyhow, bail};
+
+use swc_common::{sync::Lrc, SourceMap, FileName, Globals, Mark, Span, Spanned, SyntaxContext, DUMMY_SP, GLOBALS};
+use swc_ecma_ast::{
+    EsVersion, Module, ModuleItem, Stmt, Expr, Lit, Callee, CallExpr, Ident, BlockStmt, BinExpr, BinaryOp,
+    Function, ArrowExpr, Class, IfStmt, CondExpr, TryStmt, WhileStmt, DoWhileStmt, ForStmt, ForInStmt, ForOfStmt,
+    SwitchStmt, OptChainExpr,
+    AssignExpr, MemberExpr, MemberProp, PatOrExpr, Pat, Prop, PropName, PropOrSpread, BindingIdent, KeyValueProp, Str,
+};
+use swc_ecma_parser::{parse_file_as_module, EsConfig, Syntax};
+use swc_ecma_transforms_base::resolver;
+
+use crate::{analyze, output, paths};
+use swc_ecma_visit::{FoldWith, Visit, VisitWith, VisitMut, VisitMutWith};
+
+/// Specifier of the virtual module holding the interop helpers that the
+/// CommonJS wrappers import.
+pub const HELPERS_SPECIFIER: &str = "please-bundle:interop";
+
+/// Prefix the CommonJS wrappers give the specifiers of requires that might
+/// never run, which the resolver lets be missing.
+pub const OPTIONAL_PREFIX: &str = "please-bundle:optional:";
+
+/// Names the module standing in for an optional require that couldn't be
+/// resolved, which throws once it's required.
+pub const MISSING_PREFIX: &str = "please-bundle:missing:";
+
+/// How CommonJS `module.exports` are surfaced to ES modules, and how ES
+/// modules look to `require`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interop {
+    /// The default import is always `module.exports`, like Node.
+    Node,
+    /// Modules flagged with `__esModule` provide their own default, like
+    /// babel and webpack.
+    Babel,
+}
+
+pub fn helpers_source(interop: Interop) -> String {
+    let (mark_es_module, default_export) = match interop {
+        Interop::Node => (
+            "",
+            "return exports;",
+        ),
+        Interop::Babel => (
+            "Object.defineProperty(exports, \"__esModule\", { value: true });",
+            "return exports && exports.__esModule ? exports.default : exports;",
+        ),
+    };
+
+    format!(r#"
+export function __toCommonJS(ns) {{
+    if (ns.__cjsModule) {{
+        return ns.__cjsModule.exports;
+    }}
+    if (ns.__cjsMissing) {{
+        var error = new Error("Cannot find module '" + ns.__cjsMissing + "'");
+        error.code = "MODULE_NOT_FOUND";
+        throw error;
+    }}
+    var exports = {{}};
+    {mark_es_module}
+    Object.keys(ns).forEach(function (key) {{
+        Object.defineProperty(exports, key, {{
+            enumerable: true,
+            get: function () {{ return ns[key]; }},
+        }});
+    }});
+    return exports;
+}}
+
+export function __externalToCommonJS(ns) {{
+    if ("module.exports" in ns) {{
+        return ns["module.exports"];
+    }}
+    var exports = ns.default;
+    var isCommonJS = exports !== null && (typeof exports === "object" || typeof exports === "function")
+        && Object.keys(ns).every(function (key) {{ return key === "default" || key in exports; }});
+    return isCommonJS ? exports : __toCommonJS(ns);
+}}
+
+export function __cjsDefault(exports) {{
+    {default_export}
+}}
+"#)
+}
+
+/// A module is treated as CommonJS when it has no import or export
+/// declarations but does reach for `module`, `exports` or `require`.
+pub fn is_commonjs(module: &Module) -> bool {
+    if has_module_declarations(module) {
+        return false;
+    }
+
+    let mut detector = CommonJsDetector { found: false };
+    module.visit_with(&mut detector);
+    detector.found
+}
+
+pub fn has_module_declarations(module: &Module) -> bool {
+    module.body.iter().any(|item| matches!(item, ModuleItem::ModuleDecl(_)))
+}
+
+struct CommonJsDetector {
+    found: bool,
+}
+
+impl Visit for CommonJsDetector {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if matches!(&*ident.sym, "module" | "exports" | "require") {
+            self.found = true;
+        }
+    }
+}
+
+/// Every string literal passed to `require`, in source order and without
+/// duplicates.
+pub fn required_specifiers(module: &Module) -> Vec<String> {
+    let mut collector = RequireCollector { specifiers: vec![], resolves: false };
+    module.visit_with(&mut collector);
+    collector.specifiers
+}
+
+struct RequireCollector {
+    specifiers: Vec<String>,
+    /// Whether `require.resolve` calls count as well.
+    resolves: bool,
+}
+
+impl RequireCollector {
+    fn visit_call_expr_only(&mut self, call: &CallExpr) {
+        let specifier = require_call_specifier(call)
+            .or_else(|| self.resolves.then(|| require_resolve_specifier(call)).flatten());
+        if let Some(specifier) = specifier {
+            if !self.specifiers.contains(&specifier) {
+                self.specifiers.push(specifier);
+            }
+        }
+    }
+}
+
+impl Visit for RequireCollector {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+        self.visit_call_expr_only(call);
+    }
+}
+
+/// The specifiers of the requires `module` makes as its body runs, rather
+/// than in a function, under a condition or in a `try`, along with those of
+/// its `require.resolve` calls when `resolves` is set.
+fn unconditional_specifiers(module: &Module, resolves: bool) -> Vec<String> {
+    let mut collector = RequireCollector { specifiers: vec![], resolves };
+    for item in &module.body {
+        if let ModuleItem::Stmt(stmt) = item {
+            stmt.visit_with(&mut UnconditionalVisitor { collector: &mut collector });
+        }
+    }
+    collector.specifiers
+}
+
+/// Visits only what's certain to run when the statements it's given do.
+struct UnconditionalVisitor<'a> {
+    collector: &'a mut RequireCollector,
+}
+
+impl Visit for UnconditionalVisitor<'_> {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+        self.collector.visit_call_expr_only(call);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+
+    fn visit_try_stmt(&mut self, _: &TryStmt) {}
+
+    fn visit_opt_chain_expr(&mut self, _: &OptChainExpr) {}
+
+    fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+        stmt.test.visit_with(self);
+    }
+
+    fn visit_cond_expr(&mut self, expr: &CondExpr) {
+        expr.test.visit_with(self);
+    }
+
+    fn visit_bin_expr(&mut self, expr: &BinExpr) {
+        match expr.op {
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing => expr.left.visit_with(self),
+            _ => expr.visit_children_with(self),
+        }
+    }
+
+    fn visit_switch_stmt(&mut self, stmt: &SwitchStmt) {
+        stmt.discriminant.visit_with(self);
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+        stmt.test.visit_with(self);
+    }
+
+    fn visit_do_while_stmt(&mut self, stmt: &DoWhileStmt) {
+        stmt.body.visit_with(self);
+        stmt.test.visit_with(self);
+    }
+
+    fn visit_for_stmt(&mut self, stmt: &ForStmt) {
+        stmt.init.visit_with(self);
+        stmt.test.visit_with(self);
+    }
+
+    fn visit_for_in_stmt(&mut self, stmt: &ForInStmt) {
+        stmt.right.visit_with(self);
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        stmt.right.visit_with(self);
+    }
+}
+
+/// Names a CommonJS module statically exports, found the way
+/// cjs-module-lexer does: `exports.foo = ...`, `module.exports.foo = ...`,
+/// `Object.defineProperty(exports, "foo", ...)` and object literals assigned
+/// to `module.exports`. `module.exports = require("x")` is recorded as a
+/// re-export of `x`.
+#[derive(Default)]
+pub struct CommonJsExports {
+    pub names: Vec<String>,
+    pub reexports: Vec<String>,
+}
+
+pub fn commonjs_exports(module: &Module) -> CommonJsExports {
+    let mut collector = ExportsCollector::default();
+    module.visit_with(&mut collector);
+    collector.exports
+}
+
+#[derive(Default)]
+struct ExportsCollector {
+    exports: CommonJsExports,
+}
+
+impl ExportsCollector {
+    fn add_name(&mut self, name: &str) {
+        if name == "default" || name == "__esModule" || name == "__cjsModule" || !is_identifier_name(name) {
+            return;
+        }
+
+        if !self.exports.names.iter().any(|existing| existing == name) {
+            self.exports.names.push(name.to_string());
+        }
+    }
+}
+
+impl Visit for ExportsCollector {
+    fn visit_assign_expr(&mut self, assign: &AssignExpr) {
+        assign.visit_children_with(self);
+
+        let target = match &assign.left {
+            PatOrExpr::Expr(expr) => &**expr,
+            PatOrExpr::Pat(pat) => match &**pat {
+                Pat::Expr(expr) => &**expr,
+                _ => return,
+            },
+        };
+
+        let member = match target {
+            Expr::Member(member) => member,
+            _ => return,
+        };
+
+        if is_exports_object(&member.obj) {
+            if let Some(name) = member_prop_name(&member.prop) {
+                self.add_name(&name);
+            }
+        } else if is_ident(&member.obj, "module") && member_prop_name(&member.prop).as_deref() == Some("exports") {
+            match &*assign.right {
+                Expr::Object(object) => {
+                    for prop in &object.props {
+                        let name = match prop {
+                            PropOrSpread::Prop(prop) => match &**prop {
+                                Prop::Shorthand(ident) => Some(ident.sym.to_string()),
+                                Prop::KeyValue(kv) => prop_name(&kv.key),
+                                Prop::Method(method) => prop_name(&method.key),
+                                Prop::Getter(getter) => prop_name(&getter.key),
+                                _ => None,
+                            },
+                            PropOrSpread::Spread(_) => None,
+                        };
+
+                        if let Some(name) = name {
+                            self.add_name(&name);
+                        }
+                    }
+                },
+                Expr::Call(call) => {
+                    if let Some(specifier) = require_call_specifier(call) {
+                        self.exports.reexports.push(specifier);
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+
+        let callee = match &call.callee {
+            Callee::Expr(callee) => &**callee,
+            _ => return,
+        };
+
+        let is_define_property = matches!(
+            callee,
+            Expr::Member(MemberExpr { obj, prop, .. })
+                if is_ident(obj, "Object") && member_prop_name(prop).as_deref() == Some("defineProperty")
+        );
+
+        if is_define_property && call.args.len() >= 2 && is_exports_object(&call.args[0].expr) {
+            if let Expr::Lit(Lit::Str(name)) = &*call.args[1].expr {
+                self.add_name(&name.value);
+            }
+        }
+    }
+}
+
+fn is_ident(expr: &Expr, name: &str) -> bool {
+    matches!(expr, Expr::Ident(ident) if &*ident.sym == name)
+}
+
+/// `exports` or `module.exports`.
+fn is_exports_object(expr: &Expr) -> bool {
+    match expr {
+        Expr::Ident(ident) => &*ident.sym == "exports",
+        Expr::Member(member) => is_ident(&member.obj, "module") && member_prop_name(&member.prop).as_deref() == Some("exports"),
+        _ => false,
+    }
+}
+
+fn member_prop_name(prop: &MemberProp) -> Option<String> {
+    match prop {
+        MemberProp::Ident(ident) => Some(ident.sym.to_string()),
+        MemberProp::Computed(computed) => match &*computed.expr {
+            Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+            _ => None,
+        },
+        MemberProp::PrivateName(_) => None,
+    }
+}
+
+fn prop_name(name: &PropName) -> Option<String> {
+    match name {
+        PropName::Ident(ident) => Some(ident.sym.to_string()),
+        PropName::Str(s) => Some(s.value.to_string()),
+        _ => None,
+    }
+}
+
+fn require_call_specifier(call: &CallExpr) -> Option<String> {
+    match &call.callee {
+        Callee::Expr(callee) if is_ident(callee, "require") => match call.args.first().map(|arg| &*arg.expr) {
+            Some(Expr::Lit(Lit::Str(specifier))) => Some(specifier.value.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The string literal passed to `require.resolve`, if `call` is one.
+fn require_resolve_specifier(call: &CallExpr) -> Option<String> {
+    match &call.callee {
+        Callee::Expr(callee) if is_require_resolve(callee) => match call.args.first().map(|arg| &*arg.expr) {
+            Some(Expr::Lit(Lit::Str(specifier))) => Some(specifier.value.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_require_resolve(expr: &Expr) -> bool {
+    matches!(expr, Expr::Member(member) if is_ident(&member.obj, "require") && member_prop_name(&member.prop).as_deref() == Some("resolve"))
+}
+
+pub fn is_identifier_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_alphabetic() || first == '_' || first == '$' => {},
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Rewrites a CommonJS module into an ES module that runs the original body
+/// with its own `module`, `exports` and `require`, importing everything it
+/// requires up front and exporting `module.exports` as its default,
+/// alongside any named exports found by [commonjs_exports].
+///
+/// A require that might never run, being in a function, under a condition
+/// or in a `try`, is of a module that needn't be there, such as an optional
+/// dependency. It's bundled when it resolves and otherwise throws once it's
+/// required, as it would unbundled, though whatever is bundled is still
+/// evaluated up front.
+///
+/// With `--require-resolve=bundle`, what `require.resolve` names is bundled
+/// as if it were required, and the id it gives back is one the module's
+/// `require` takes, as with webpack. With `--require-resolve=external` it's
+/// handed to the runtime's own `require`, which fails the build when
+/// `no_runtime_require` says why there isn't one.
+///
+/// Requiring one of `externals` gives its `module.exports` as Node does,
+/// which Node makes the default export of CommonJS modules and builtins, and
+/// only ES modules come as a copy of their namespace.
+pub fn wrap_commonjs(
+    cm: &Lrc<SourceMap>,
+    module: Module,
+    require_resolve: RequireResolve,
+    no_runtime_require: Option<&str>,
+    externals: &[String],
+) -> Result<Module, Error> {
+    let resolves = require_resolves(&module);
+    match (require_resolve, resolves.first()) {
+        (_, None) => {},
+        (RequireResolve::Error, Some((span, _))) => {
+            bail!("{}: require.resolve has nothing to resolve to once bundled, pass --require-resolve=bundle to bundle what it names or --require-resolve=external to leave it to the runtime", analyze::location(cm, *span));
+        },
+        (RequireResolve::External, Some((span, _))) => if let Some(why) = no_runtime_require {
+            bail!("{}: --require-resolve=external leaves require.resolve to the runtime, but {why}", analyze::location(cm, *span));
+        },
+        (RequireResolve::Bundle, _) => if let Some((span, _)) = resolves.iter().find(|(_, specifier)| specifier.is_none()) {
+            bail!("{}: require.resolve of a computed specifier can't be bundled", analyze::location(cm, *span));
+        },
+    }
+
+    let bundle_resolves = require_resolve == RequireResolve::Bundle;
+    let specifiers = if bundle_resolves {
+        let mut collector = RequireCollector { specifiers: vec![], resolves: true };
+        module.visit_with(&mut collector);
+        collector.specifiers
+    } else {
+        required_specifiers(&module)
+    };
+    let exports = commonjs_exports(&module);
+    let unconditional = unconditional_specifiers(&module, bundle_resolves);
+
+    // Both `require` and, with --require-resolve=bundle, `require.resolve`
+    // pick out what was imported for the specifier they're given.
+    let lookup = |found: &dyn Fn(String) -> String| {
+        let mut src = String::from("function (specifier) {\n    switch (specifier) {\n");
+        for (index, specifier) in specifiers.iter().enumerate() {
+            let helper = if externals.contains(specifier) { "__externalToCommonJS" } else { "__toCommonJS" };
+            src.push_str(&format!("        case {specifier:?}: {};\n", found(format!("{helper}(__cjs_require_{index})"))));
+        }
+        src.push_str("    }\n    throw new Error(\"Cannot find module '\" + specifier + \"'\");\n}");
+        src
+    };
+    let resolve = match require_resolve {
+        _ if resolves.is_empty() => None,
+        RequireResolve::Error => None,
+        RequireResolve::External => Some(String::from("function (specifier) { return require.resolve(specifier); }")),
+        RequireResolve::Bundle => Some(lookup(&|exports| format!("{exports}; return specifier"))),
+    };
+
+    let mut src = String::new();
+    for (index, specifier) in specifiers.iter().enumerate() {
+        let source = if unconditional.contains(specifier) || externals.contains(specifier) {
+            specifier.clone()
+        } else {
+            format!("{OPTIONAL_PREFIX}{specifier}")
+        };
+        src.push_str(&format!("import * as __cjs_require_{index} from {source:?};\n"));
+    }
+    src.push_str(&format!("import {{ __toCommonJS, __externalToCommonJS, __cjsDefault }} from {HELPERS_SPECIFIER:?};\n"));
+    src.push_str("var __cjs_module = { exports: {} };\n");
+    src.push_str("(function (module, exports, require) {\n    \"please-bundle:body\";\n})");
+    src.push_str(".call(__cjs_module.exports, __cjs_module, __cjs_module.exports, ");
+    if resolve.is_some() {
+        src.push_str("Object.assign(");
+    }
+    src.push_str(&lookup(&|exports| format!("return {exports}")));
+    if let Some(resolve) = resolve {
+        src.push_str(&format!(", {{ resolve: {resolve} }})"));
+    }
+    src.push_str(");\n");
+    src.push_str("export default __cjsDefault(__cjs_module.exports);\n");
+    src.push_str("export { __cjs_module as __cjsModule };\n");
+    for (index, name) in exports.names.iter().enumerate() {
+        src.push_str(&format!("var __cjs_export_{index} = __cjs_module.exports.{name};\n"));
+        src.push_str(&format!("export {{ __cjs_export_{index} as {name} }};\n"));
+    }
+    for specifier in &exports.reexports {
+        src.push_str(&format!("export * from {specifier:?};\n"));
+    }
+
+    let mut wrapper = parse_generated(cm, "please-bundle:cjs-wrapper", src)?;
+
+    let body = module.body.into_iter()
+        .filter_map(|item| match item {
+            ModuleItem::Stmt(stmt) => Some(stmt),
+            ModuleItem::ModuleDecl(_) => None,
+        })
+        .collect();
+
+    let mut injector = BodyInjector { body: Some(body) };
+    wrapper.visit_mut_with(&mut injector);
+    if injector.body.is_some() {
+        return Err(anyhow!("failed to find the body placeholder in the commonjs wrapper"));
+    }
+
+    Ok(wrapper)
+}
+
+/// What's done with `require.resolve` in CommonJS modules, which asks for
+/// a path there's no file at once the module is bundled.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequireResolve {
+    /// Fail the build.
+    Error,
+    /// Hand it to the runtime's own `require.resolve`, so what it gives is
+    /// found relative to the bundle.
+    External,
+    /// Bundle the module it names, giving back an id `require` takes for it.
+    Bundle,
+}
+
+/// The span of every `require.resolve` in `module`, with the specifier it's
+/// called with when that's a string literal.
+fn require_resolves(module: &Module) -> Vec<(Span, Option<String>)> {
+    let mut finder = RequireResolveFinder { resolves: vec![] };
+    module.visit_with(&mut finder);
+    finder.resolves
+}
+
+struct RequireResolveFinder {
+    resolves: Vec<(Span, Option<String>)>,
+}
+
+impl Visit for RequireResolveFinder {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        match &call.callee {
+            Callee::Expr(callee) if is_require_resolve(callee) => {
+                self.resolves.push((callee.span(), require_resolve_specifier(call)));
+                call.args.visit_with(self);
+            },
+            _ => call.visit_children_with(self),
+        }
+    }
+
+    fn visit_member_expr(&mut self, member: &MemberExpr) {
+        if is_ident(&member.obj, "require") && member_prop_name(&member.prop).as_deref() == Some("resolve") {
+            self.resolves.push((member.span, None));
+        }
+
+        member.visit_children_with(self);
+    }
+}
+
+/// The module standing in for `specifier` when an optional require of it
+/// couldn't be resolved.
+pub fn missing_source(specifier: &str) -> String {
+    format!("export var __cjsMissing = {specifier:?};\n")
+}
+
+/// Parses code generated by the bundler itself. Spans are dropped so the
+/// generated code doesn't show up as a source in the source map.
+pub fn parse_generated(cm: &Lrc<SourceMap>, name: &str, src: String) -> Result<Module, Error> {
+    let fm = cm.new_source_file(FileName::Custom(name.to_string()), src);
+
+    let mut module = parse_file_as_module(
+        &fm,
+        Syntax::Es(EsConfig {
+            ..Default::default()
+        }),
+        EsVersion::Es2020,
+        None,
+        &mut vec![],
+    )
+    .map_err(|err| anyhow!("failed to parse generated code for {name}: {:?}", err.kind()))?;
+
+    module.visit_mut_with(&mut SpanDropper);
+    Ok(module)
+}
+
+struct SpanDropper;
+
+impl VisitMut for SpanDropper {
+    fn visit_mut_span(&mut self, span: &mut Span) {
+        *span = DUMMY_SP;
+    }
+}
+
+struct BodyInjector {
+    body: Option<Vec<Stmt>>,
+}
+
+impl VisitMut for BodyInjector {
+    fn visit_mut_block_stmt(&mut self, block: &mut BlockStmt) {
+        let is_placeholder = matches!(
+            block.stmts.as_slice(),
+            [Stmt::Expr(stmt)] if matches!(&*stmt.expr, Expr::Lit(Lit::Str(s)) if &*s.value == "please-bundle:body")
+        );
+
+        if is_placeholder {
+            if let Some(body) = self.body.take() {
+                block.stmts = body;
+                return;
+            }
+        }
+
+        block.visit_mut_children_with(self);
+    }
+}
+
+/// What `__dirname` and `__filename` are in bundled code.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dirname {
+    /// Left alone, for runtimes that provide them.
+    Keep,
+    /// Those of the bundle, worked out from `import.meta.url` as Node
+    /// would for a CommonJS module.
+    ImportMeta,
+    /// The path of each module's own source, relative to the directory
+    /// the build runs in.
+    Relative,
+}
+
+/// Gives an ES module for Node the CommonJS globals it reaches for without
+/// declaring, as ES modules don't have them: a `require` made with
+/// `createRequire(import.meta.url)` when `require` is asked for, and
+/// `__dirname` and `__filename` from `import.meta.url` when `dirname` is.
+/// Globals the module doesn't use are left out.
+pub fn inject_node_globals(globals: &Globals, mut module: Module, require: bool, dirname: bool) -> Result<Module, Error> {
+    let free = GLOBALS.set(globals, || {
+        let unresolved_mark = Mark::new();
+        let resolved = module.clone().fold_with(&mut resolver(unresolved_mark, Mark::new(), false));
+
+        let mut finder = FreeIdentFinder { unresolved: SyntaxContext::empty().apply_mark(unresolved_mark), found: vec![] };
+        resolved.visit_with(&mut finder);
+        finder.found
+    });
+    let uses = |name: &str| free.iter().any(|found| found == name);
+
+    let mut src = String::new();
+    if require && uses("require") {
+        src.push_str("import { createRequire as __createRequire } from \"node:module\";\n");
+        src.push_str("const require = __createRequire(import.meta.url);\n");
+    }
+    if dirname && (uses("__filename") || uses("__dirname")) {
+        src.push_str("import { fileURLToPath as __fileURLToPath } from \"node:url\";\n");
+        src.push_str("import { dirname as __pathDirname } from \"node:path\";\n");
+        if uses("__filename") {
+            src.push_str("const __filename = __fileURLToPath(import.meta.url);\n");
+        }
+        if uses("__dirname") {
+            src.push_str("const __dirname = __pathDirname(__fileURLToPath(import.meta.url));\n");
+        }
+    }
+
+    if src.is_empty() {
+        return Ok(module);
+    }
+
+    // The preamble's spans are dropped, so it doesn't matter which source
+    // map it's parsed with.
+    let preamble = parse_generated(&Default::default(), "please-bundle:node-globals", src)?;
+    module.body.splice(0..0, preamble.body);
+    Ok(module)
+}
+
+struct FreeIdentFinder {
+    unresolved: SyntaxContext,
+    found: Vec<String>,
+}
+
+impl Visit for FreeIdentFinder {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if ident.span.ctxt == self.unresolved && matches!(&*ident.sym, "require" | "__filename" | "__dirname") {
+            self.found.push(ident.sym.to_string());
+        }
+    }
+}
+
+/// Replaces `__dirname` and `__filename` in `module` with the directory and
+/// path of `path`, relative to the directory the build runs in. A module
+/// declaring either itself is left alone.
+pub fn inline_dirname(module: &mut Module, path: &Path) -> Result<(), Error> {
+    let mut declared = DeclaredFinder { found: false };
+    module.visit_with(&mut declared);
+    if declared.found {
+        return Ok(());
+    }
+
+    let cwd = paths::current_dir()?;
+    let filename = paths::to_slash(&output::relative_path(&cwd.join(path), &cwd));
+    let dirname = match filename.rsplit_once('/') {
+        Some((dir, _)) => dir.to_string(),
+        None => String::from("."),
+    };
+
+    module.visit_mut_with(&mut DirnameInliner { filename, dirname });
+    Ok(())
+}
+
+struct DeclaredFinder {
+    found: bool,
+}
+
+impl Visit for DeclaredFinder {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        if matches!(&*ident.id.sym, "__filename" | "__dirname") {
+            self.found = true;
+        }
+    }
+}
+
+struct DirnameInliner {
+    filename: String,
+    dirname: String,
+}
+
+impl VisitMut for DirnameInliner {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        let value = match expr {
+            Expr::Ident(ident) if &*ident.sym == "__filename" => &self.filename,
+            Expr::Ident(ident) if &*ident.sym == "__dirname" => &self.dirname,
+            _ => return expr.visit_mut_children_with(self),
+        };
+
+        *expr = Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: value.as_str().into(), raw: None }));
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            if matches!(&*ident.sym, "__filename" | "__dirname") {
+                let mut value = Box::new(Expr::Ident(ident.clone()));
+                value.visit_mut_with(self);
+                *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value });
+                return;
+            }
+        }
+
+        prop.visit_mut_children_with(self);
+    }
+}
@@ -0,0 +1,319 @@
+use swc_common::DUMMY_SP;
+use swc_ecma_ast::{
+    ArrayLit, CallExpr, Callee, Expr, ExprOrSpread, Ident, ImportDecl, ImportNamedSpecifier, ImportSpecifier,
+    JSXAttrName, JSXAttrOrSpread, JSXAttrValue, JSXElement, JSXElementChild, JSXElementName, JSXExpr, JSXFragment,
+    JSXObject, KeyValueProp, Lit, MemberExpr, MemberProp, Module, ModuleDecl, ModuleExportName, ModuleItem, Null,
+    ObjectLit, Prop, PropName, PropOrSpread, Str,
+};
+use swc_ecma_visit::{VisitMut, VisitMutWith};
+
+/// Expressions JSX is compiled to, e.g. `React.createElement`, or with an
+/// import source the package whose `jsx-runtime` it's compiled against.
+pub struct Options {
+    pub factory: String,
+    pub fragment: String,
+    pub import_source: Option<String>,
+}
+
+/// Compiles JSX into calls of the factory, the classic React transform:
+/// `<a href="/">{x}</a>` becomes `React.createElement("a", { href: "/" }, x)`.
+///
+/// With an import source it's the automatic runtime instead, which imports
+/// what it needs from `<source>/jsx-runtime` and passes children as a prop:
+/// `<a href="/">{x}</a>` becomes `__jsx("a", { href: "/", children: x })`.
+pub fn transform_jsx(module: &mut Module, options: &Options) {
+    let mut transform = JsxTransform { options, runtime_imports: vec![] };
+    module.visit_mut_with(&mut transform);
+
+    if let (Some(source), false) = (&options.import_source, transform.runtime_imports.is_empty()) {
+        let mut imports = transform.runtime_imports;
+        imports.sort();
+
+        let specifiers = imports.into_iter()
+            .map(|name| ImportSpecifier::Named(ImportNamedSpecifier {
+                span: DUMMY_SP,
+                local: Ident::new(format!("__{name}").into(), DUMMY_SP),
+                imported: Some(ModuleExportName::Ident(Ident::new(name.into(), DUMMY_SP))),
+                is_type_only: false,
+            }))
+            .collect();
+
+        module.body.insert(0, ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+            span: DUMMY_SP,
+            specifiers,
+            src: Box::new(Str::from(format!("{source}/jsx-runtime"))),
+            type_only: false,
+            asserts: None,
+        })));
+    }
+}
+
+struct JsxTransform<'a> {
+    options: &'a Options,
+    /// What the automatic runtime has been asked for so far.
+    runtime_imports: Vec<&'static str>,
+}
+
+impl JsxTransform<'_> {
+    fn element(&mut self, element: JSXElement) -> Expr {
+        let tag = match element.opening.name {
+            JSXElementName::Ident(ident) if is_intrinsic(&ident.sym) => string(&ident.sym),
+            JSXElementName::Ident(ident) => Expr::Ident(ident),
+            JSXElementName::JSXMemberExpr(member) => jsx_object(JSXObject::JSXMemberExpr(Box::new(member))),
+            JSXElementName::JSXNamespacedName(name) => string(&format!("{}:{}", name.ns.sym, name.name.sym)),
+        };
+
+        if self.options.import_source.is_some() {
+            let mut key = None;
+            let mut props = vec![];
+            for attr in element.opening.attrs {
+                match attr {
+                    JSXAttrOrSpread::JSXAttr(attr) if matches!(&attr.name, JSXAttrName::Ident(name) if &*name.sym == "key") => {
+                        key = Some(self.attr_value(attr.value));
+                    },
+                    attr => props.push(self.prop(attr)),
+                }
+            }
+            return self.runtime_call(tag, props, element.children, key);
+        }
+
+        let props = if element.opening.attrs.is_empty() {
+            Expr::Lit(Lit::Null(Null { span: DUMMY_SP }))
+        } else {
+            Expr::Object(ObjectLit {
+                span: DUMMY_SP,
+                props: element.opening.attrs.into_iter().map(|attr| self.prop(attr)).collect(),
+            })
+        };
+
+        self.call(&self.options.factory, tag, props, element.children)
+    }
+
+    fn fragment(&mut self, fragment: JSXFragment) -> Expr {
+        if self.options.import_source.is_some() {
+            let tag = self.runtime("Fragment");
+            return self.runtime_call(tag, vec![], fragment.children, None);
+        }
+
+        let tag = dotted(&self.options.fragment);
+        let props = Expr::Lit(Lit::Null(Null { span: DUMMY_SP }));
+        self.call(&self.options.factory, tag, props, fragment.children)
+    }
+
+    /// The automatic runtime's `jsx(tag, props, key)`, or `jsxs` when there
+    /// are several children.
+    fn runtime_call(&mut self, tag: Expr, mut props: Vec<PropOrSpread>, children: Vec<JSXElementChild>, key: Option<Expr>) -> Expr {
+        let mut children: Vec<ExprOrSpread> = children.into_iter().filter_map(|child| self.child(child)).collect();
+        let callee = self.runtime(if children.len() > 1 { "jsxs" } else { "jsx" });
+
+        let children_value = match children.len() {
+            0 => None,
+            1 if children[0].spread.is_none() => Some(*children.remove(0).expr),
+            _ => Some(Expr::Array(ArrayLit { span: DUMMY_SP, elems: children.into_iter().map(Some).collect() })),
+        };
+        if let Some(value) = children_value {
+            props.push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident(Ident::new("children".into(), DUMMY_SP)),
+                value: Box::new(value),
+            }))));
+        }
+
+        let mut args = vec![
+            ExprOrSpread { spread: None, expr: Box::new(tag) },
+            ExprOrSpread { spread: None, expr: Box::new(Expr::Object(ObjectLit { span: DUMMY_SP, props })) },
+        ];
+        if let Some(key) = key {
+            args.push(ExprOrSpread { spread: None, expr: Box::new(key) });
+        }
+
+        Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(callee)),
+            args,
+            type_args: None,
+        })
+    }
+
+    /// The local name of an export of the automatic runtime, noting that
+    /// it needs importing.
+    fn runtime(&mut self, name: &'static str) -> Expr {
+        if !self.runtime_imports.contains(&name) {
+            self.runtime_imports.push(name);
+        }
+        Expr::Ident(Ident::new(format!("__{name}").into(), DUMMY_SP))
+    }
+
+    fn call(&mut self, factory: &str, tag: Expr, props: Expr, children: Vec<JSXElementChild>) -> Expr {
+        let mut args = vec![
+            ExprOrSpread { spread: None, expr: Box::new(tag) },
+            ExprOrSpread { spread: None, expr: Box::new(props) },
+        ];
+        args.extend(children.into_iter().filter_map(|child| self.child(child)));
+
+        Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(dotted(factory))),
+            args,
+            type_args: None,
+        })
+    }
+
+    fn prop(&mut self, attr: JSXAttrOrSpread) -> PropOrSpread {
+        let attr = match attr {
+            JSXAttrOrSpread::JSXAttr(attr) => attr,
+            JSXAttrOrSpread::SpreadElement(spread) => return PropOrSpread::Spread(spread),
+        };
+
+        let key = match attr.name {
+            JSXAttrName::Ident(ident) if !ident.sym.contains('-') => PropName::Ident(ident),
+            JSXAttrName::Ident(ident) => PropName::Str(Str::from(ident.sym.to_string())),
+            JSXAttrName::JSXNamespacedName(name) => PropName::Str(Str::from(format!("{}:{}", name.ns.sym, name.name.sym))),
+        };
+
+        let value = self.attr_value(attr.value);
+
+        PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp { key, value: Box::new(value) })))
+    }
+
+    fn attr_value(&mut self, value: Option<JSXAttrValue>) -> Expr {
+        match value {
+            None => Expr::Lit(Lit::Bool(true.into())),
+            Some(JSXAttrValue::Lit(lit)) => Expr::Lit(lit),
+            Some(JSXAttrValue::JSXExprContainer(container)) => match container.expr {
+                JSXExpr::Expr(expr) => *expr,
+                JSXExpr::JSXEmptyExpr(_) => Expr::Lit(Lit::Bool(true.into())),
+            },
+            Some(JSXAttrValue::JSXElement(element)) => self.element(*element),
+            Some(JSXAttrValue::JSXFragment(fragment)) => self.fragment(fragment),
+        }
+    }
+
+    fn child(&mut self, child: JSXElementChild) -> Option<ExprOrSpread> {
+        let expr = match child {
+            JSXElementChild::JSXText(text) => string(&clean_text(&text.value)?),
+            JSXElementChild::JSXExprContainer(container) => match container.expr {
+                JSXExpr::Expr(expr) => *expr,
+                JSXExpr::JSXEmptyExpr(_) => return None,
+            },
+            JSXElementChild::JSXSpreadChild(spread) => {
+                return Some(ExprOrSpread { spread: Some(DUMMY_SP), expr: spread.expr });
+            },
+            JSXElementChild::JSXElement(element) => self.element(*element),
+            JSXElementChild::JSXFragment(fragment) => self.fragment(fragment),
+        };
+
+        Some(ExprOrSpread { spread: None, expr: Box::new(expr) })
+    }
+}
+
+impl VisitMut for JsxTransform<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        expr.visit_mut_children_with(self);
+
+        let transformed = match std::mem::replace(expr, Expr::Invalid(swc_ecma_ast::Invalid { span: DUMMY_SP })) {
+            Expr::JSXElement(element) => self.element(*element),
+            Expr::JSXFragment(fragment) => self.fragment(fragment),
+            other => other,
+        };
+        *expr = transformed;
+    }
+}
+
+/// Lower case tags, and any with a dash, are HTML elements rather than
+/// components in scope.
+fn is_intrinsic(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase()) || name.contains('-')
+}
+
+fn jsx_object(object: JSXObject) -> Expr {
+    match object {
+        JSXObject::Ident(ident) => Expr::Ident(ident),
+        JSXObject::JSXMemberExpr(member) => Expr::Member(MemberExpr {
+            span: DUMMY_SP,
+            obj: Box::new(jsx_object(member.obj)),
+            prop: MemberProp::Ident(member.prop),
+        }),
+    }
+}
+
+/// An expression for a dotted path such as `React.createElement`.
+fn dotted(path: &str) -> Expr {
+    let mut parts = path.split('.');
+    let first = Expr::Ident(Ident::new(parts.next().unwrap_or_default().into(), DUMMY_SP));
+
+    parts.fold(first, |obj, part| Expr::Member(MemberExpr {
+        span: DUMMY_SP,
+        obj: Box::new(obj),
+        prop: MemberProp::Ident(Ident::new(part.into(), DUMMY_SP)),
+    }))
+}
+
+fn string(value: &str) -> Expr {
+    Expr::Lit(Lit::Str(Str::from(value.to_string())))
+}
+
+/// JSX text the way React reads it: lines are trimmed where they meet other
+/// lines, blank ones dropped and the rest joined with a space. `None` when
+/// nothing is left.
+fn clean_text(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let last_non_empty = lines.iter().rposition(|line| !line.trim().is_empty())?;
+
+    let mut cleaned = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let mut line = *line;
+        if index != 0 {
+            line = line.trim_start();
+        }
+        if index != lines.len() - 1 {
+            line = line.trim_end();
+        }
+
+        if !line.is_empty() {
+            cleaned.push_str(line);
+            if index != last_non_empty {
+                cleaned.push(' ');
+            }
+        }
+    }
+
+    Some(decode_entities(&cleaned))
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let character = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => name.strip_prefix('#').and_then(|decimal| decimal.parse().ok()).and_then(char::from_u32),
+            },
+        });
+
+        match (character, entity) {
+            (Some(character), Some((_, end))) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            },
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
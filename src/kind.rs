@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use swc_common::sync::Lock;
+
+/// The module semantics a file is given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModuleKind {
+    Esm,
+    CommonJs,
+}
+
+/// The kind a file's extension settles, whatever its package declares.
+pub fn from_extension(path: &Path) -> Option<ModuleKind> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("mjs" | "mts") => Some(ModuleKind::Esm),
+        Some("cjs" | "cts") => Some(ModuleKind::CommonJs),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct PackageType {
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+}
+
+/// Looks up the `"type"` of the package owning a file, caching the nearest
+/// `package.json` of every directory asked about.
+#[derive(Default)]
+pub struct PackageTypes {
+    by_dir: Lock<HashMap<PathBuf, Option<ModuleKind>>>,
+}
+
+impl PackageTypes {
+    /// The kind the nearest `package.json` declares for `path`. As in Node,
+    /// a package without a `"type"` is CommonJS, though its files with
+    /// imports or exports are still ES modules, as Node detects them. A file
+    /// outside any package declares nothing.
+    pub fn declared(&self, path: &Path) -> Option<ModuleKind> {
+        self.declared_in(path.parent()?)
+    }
+
+    fn declared_in(&self, dir: &Path) -> Option<ModuleKind> {
+        if let Some(kind) = self.by_dir.borrow().get(dir) {
+            return *kind;
+        }
+
+        let package_path = dir.join("package.json");
+        let kind = if package_path.exists() {
+            std::fs::read_to_string(&package_path).ok()
+                .and_then(|contents| serde_json::from_str::<PackageType>(&contents).ok())
+                .map(|package| match package.kind.as_deref() {
+                    Some("module") => ModuleKind::Esm,
+                    _ => ModuleKind::CommonJs,
+                })
+        } else {
+            dir.parent().and_then(|parent| self.declared_in(parent))
+        };
+
+        self.by_dir.borrow_mut().insert(dir.to_path_buf(), kind);
+        kind
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use anyhow::{Error, anyhow};
+
+use serde::{Deserialize, Serialize};
+
+/// What a bundled package says it's licensed under, from its
+/// `package.json` and the license files it ships.
+#[derive(Serialize)]
+pub struct PackageLicense {
+    pub name: String,
+    pub version: Option<String>,
+    /// An SPDX expression such as `MIT` or `(MIT OR Apache-2.0)`.
+    pub license: Option<String>,
+    #[serde(rename = "licenseFiles")]
+    pub license_files: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PackageFields {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    license: Option<LicenseField>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LicenseField {
+    Spdx(String),
+    /// The deprecated `{ "type": "MIT", "url": "..." }` form.
+    Legacy {
+        #[serde(rename = "type")]
+        kind: String,
+    },
+}
+
+impl PackageLicense {
+    pub fn read(package_dir: &Path) -> Result<PackageLicense, Error> {
+        let package_path = package_dir.join("package.json");
+        let package: PackageFields = serde_json::from_str(&std::fs::read_to_string(&package_path)?)
+            .map_err(|why| anyhow!("failed to parse {package_path:?}: {why}"))?;
+
+        let mut license_files: Vec<String> = std::fs::read_dir(package_dir)?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| {
+                let name = name.to_ascii_uppercase();
+                ["LICENSE", "LICENCE", "COPYING"].iter().any(|prefix| name.starts_with(prefix))
+            })
+            .collect();
+        license_files.sort();
+
+        Ok(PackageLicense {
+            name: match package.name {
+                Some(name) => name,
+                None => package_dir.display().to_string(),
+            },
+            version: package.version,
+            license: package.license.map(|license| match license {
+                LicenseField::Spdx(expression) => expression,
+                LicenseField::Legacy { kind } => kind,
+            }),
+            license_files,
+        })
+    }
+
+    /// Whether the license can't be used without taking one of the `denied`
+    /// licenses. A denied `GPL` covers `GPL-2.0`, `GPL-3.0-only` and so on
+    /// but not `LGPL-2.1`. A choice given with `OR` is denied only when
+    /// every alternative is, nesting aside.
+    pub fn is_denied(&self, denied: &[String]) -> bool {
+        let expression = match &self.license {
+            Some(expression) => expression.replace(['(', ')'], " "),
+            None => return false,
+        };
+
+        expression.split(" OR ").all(|alternative| {
+            alternative.split(" AND ")
+                .map(|id| id.split(" WITH ").next().unwrap_or_default().trim().trim_end_matches('+'))
+                .any(|id| denied.iter().any(|denied| id == denied || id.starts_with(&format!("{denied}-"))))
+        })
+    }
+
+    /// How the package is named in messages, `name@version`.
+    pub fn id(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{version}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
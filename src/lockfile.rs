@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::{Error, anyhow};
+
+use serde::Deserialize;
+
+/// The package versions a `package-lock.json` or `pnpm-lock.yaml` pins,
+/// for checking the packages handed to the bundler haven't drifted.
+pub struct Lockfile {
+    packages: BTreeMap<String, Vec<Locked>>,
+}
+
+struct Locked {
+    version: String,
+    integrity: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PackageLock {
+    /// Lockfile v2 and v3, keyed by install path, e.g.
+    /// `node_modules/a/node_modules/b`.
+    #[serde(default)]
+    packages: HashMap<String, PackageLockEntry>,
+
+    /// Lockfile v1, keyed by name.
+    #[serde(default)]
+    dependencies: HashMap<String, PackageLockEntry>,
+}
+
+#[derive(Deserialize)]
+struct PackageLockEntry {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    integrity: Option<String>,
+    #[serde(default)]
+    dependencies: HashMap<String, PackageLockEntry>,
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Lockfile, Error> {
+        let contents = std::fs::read_to_string(path)?;
+
+        let mut lockfile = Lockfile { packages: BTreeMap::new() };
+        if path.extension().is_some_and(|extension| extension == "yaml" || extension == "yml") {
+            lockfile.add_pnpm(&contents);
+        } else {
+            let lock: PackageLock = serde_json::from_str(&contents)
+                .map_err(|why| anyhow!("failed to parse lockfile {path:?}: {why}"))?;
+            lockfile.add_package_lock(lock);
+        }
+
+        Ok(lockfile)
+    }
+
+    fn add(&mut self, name: &str, version: &str, integrity: Option<String>) {
+        self.packages.entry(name.to_string()).or_default().push(Locked {
+            version: version.to_string(),
+            integrity,
+        });
+    }
+
+    fn add_package_lock(&mut self, lock: PackageLock) {
+        for (install_path, entry) in lock.packages {
+            let name = match (&entry.name, install_path.rsplit_once("node_modules/")) {
+                (Some(name), _) => name.clone(),
+                (None, Some((_, name))) => name.to_string(),
+                (None, None) => continue,
+            };
+            if let Some(version) = &entry.version {
+                self.add(&name, version, entry.integrity.clone());
+            }
+        }
+
+        fn add_dependencies(lockfile: &mut Lockfile, dependencies: HashMap<String, PackageLockEntry>) {
+            for (name, entry) in dependencies {
+                if let Some(version) = &entry.version {
+                    lockfile.add(&name, version, entry.integrity.clone());
+                }
+                add_dependencies(lockfile, entry.dependencies);
+            }
+        }
+        add_dependencies(self, lock.dependencies);
+    }
+
+    /// Reads the keys of the `packages:` section, which look like
+    /// `/name/1.0.0:` (v5), `/name@1.0.0:` (v6) or `name@1.0.0:` (v9),
+    /// possibly with a peer dependency suffix, along with any
+    /// `resolution: {integrity: ...}` beneath them. This is line based
+    /// rather than a full YAML parse.
+    fn add_pnpm(&mut self, contents: &str) {
+        let mut in_packages = false;
+        let mut current: Option<(String, String)> = None;
+
+        for line in contents.lines() {
+            if !line.starts_with(' ') && !line.trim().is_empty() {
+                if let Some((name, version)) = current.take() {
+                    self.add(&name, &version, None);
+                }
+                in_packages = line.trim_end() == "packages:";
+                continue;
+            }
+            if !in_packages {
+                continue;
+            }
+
+            let indent = line.len() - line.trim_start().len();
+            let line = line.trim();
+
+            if indent == 2 && line.ends_with(':') {
+                if let Some((name, version)) = current.take() {
+                    self.add(&name, &version, None);
+                }
+                current = parse_pnpm_key(line.trim_end_matches(':').trim_matches(['\'', '"']));
+            } else if let Some(integrity) = line.strip_prefix("resolution: {integrity: ") {
+                if let Some((name, version)) = current.take() {
+                    let integrity = integrity.split([',', '}']).next().unwrap_or_default().trim();
+                    self.add(&name, &version, Some(integrity.to_string()));
+                }
+            }
+        }
+
+        if let Some((name, version)) = current {
+            self.add(&name, &version, None);
+        }
+    }
+
+    /// The integrity the lockfile has for `name@version`, when it has one.
+    pub fn integrity(&self, name: &str, version: &str) -> Option<&str> {
+        self.packages.get(name)?
+            .iter()
+            .find(|locked| locked.version == version)?
+            .integrity
+            .as_deref()
+    }
+
+    /// Why the package doesn't match the lockfile, if it doesn't. The
+    /// integrity is only compared when both sides know it.
+    pub fn check(&self, name: &str, version: Option<&str>, integrity: Option<&str>) -> Option<String> {
+        let version = match version {
+            Some(version) => version,
+            None => return Some(format!("{name} has no version to check against the lockfile")),
+        };
+
+        let locked = match self.packages.get(name) {
+            Some(locked) => locked,
+            None => return Some(format!("{name}@{version} isn't in the lockfile")),
+        };
+
+        let locked_version = match locked.iter().find(|locked| locked.version == version) {
+            Some(locked_version) => locked_version,
+            None => {
+                let versions: Vec<&str> = locked.iter().map(|locked| locked.version.as_str()).collect();
+                return Some(format!("{name}@{version} doesn't match the lockfile's {}", versions.join(", ")));
+            },
+        };
+
+        match (integrity, &locked_version.integrity) {
+            (Some(integrity), Some(locked_integrity)) if integrity != locked_integrity => {
+                Some(format!("{name}@{version} has integrity {integrity} but the lockfile has {locked_integrity}"))
+            },
+            _ => None,
+        }
+    }
+}
+
+fn parse_pnpm_key(key: &str) -> Option<(String, String)> {
+    let key = key.trim_start_matches('/');
+    let key = key.split('(').next()?;
+
+    // v5 keys separate the version with a slash, and any peer dependencies
+    // with an underscore after it.
+    let segments: Vec<&str> = key.split('/').collect();
+    let name_segments = if key.starts_with('@') { 2 } else { 1 };
+    if segments.len() == name_segments + 1 {
+        let version = segments[name_segments].split('_').next()?;
+        return Some((segments[..name_segments].join("/"), version.to_string()));
+    }
+
+    let i = key[1..].find('@')? + 1;
+    Some((key[..i].to_string(), key[i + 1..].to_string()))
+}
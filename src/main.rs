@@ -1,32 +1,79 @@
-use std::{collections::HashMap};
-use std::io::{Read, BufWriter};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Read;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::fs::File;
 use std::str::FromStr;
+use std::sync::Mutex;
 
 use anyhow::{Error, anyhow, bail};
 
 use serde::Deserialize;
 
-use swc_bundler::{Bundler, Load, Resolve, ModuleData};
+use swc_bundler::{Bundler, BundleKind, Load, Resolve, ModuleData};
 use swc_common::{
     errors::{ColorConfig, Handler},
-    sync::Lrc, 
-    Globals, SourceMap, FilePathMapping, FileName,
+    sync::{Lock, Lrc},
+    source_map::LineCol, BytePos, Globals, SourceFile, SourceMap, FilePathMapping, FileName, Spanned,
 };
 
-use swc_ecma_ast::{EsVersion};
+use swc_ecma_ast::{EsVersion, Module};
 use swc_ecma_codegen::{
     text_writer::{JsWriter, WriteJs},
     Emitter,
 };
-use swc_ecma_parser::{parse_file_as_module, EsConfig, Syntax};
+use swc_ecma_parser::parse_file_as_module;
 
-use clap::Parser;
+use clap::{ArgGroup, Parser};
+
+mod analyze;
+mod compress;
+mod computed;
+mod config;
+mod consts;
+mod csp;
+mod dedupe;
+mod dynamic;
+mod enums;
+mod format;
+mod graph;
+mod import_map;
+mod integrity;
+mod interop;
+mod jsx;
+mod kind;
+mod licenses;
+mod lockfile;
+mod mangle;
+mod output;
+mod paths;
+mod platform;
+mod policy;
+mod peers;
+mod pragmas;
+mod provenance;
+mod remote;
+mod sbom;
+mod syntax;
+mod trace;
+mod transform;
+mod typescript;
+mod versions;
+mod virtuals;
+
+use computed::ComputedSpecifiers;
+use format::Format;
+use graph::{Check, ModuleGraph};
+use import_map::ImportMap;
+use interop::{Dirname, Interop, RequireResolve};
+use platform::Platform;
+use syntax::Language;
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_override_self = true)]
+#[command(group(ArgGroup::new("mangling").args(["mangle", "minified"]).multiple(true)))]
 struct Args {
    #[arg(short, long, default_value_t = String::from("bundle.js"))]
    output: String,
@@ -34,35 +81,325 @@ struct Args {
    #[arg(short, long)]
    map: Option<String>,
 
+   #[arg(long, conflicts_with = "map")]
+   envelope: bool,
+
    #[arg(short, long = "package")]
    packages: Vec<String>,
 
+   #[arg(long = "packages-manifest")]
+   packages_manifests: Vec<String>,
+
+   #[arg(long)]
+   lockfile: Option<String>,
+
+   #[arg(long, requires = "lockfile")]
+   package_tarballs: Option<String>,
+
+   #[arg(long, value_enum, default_value_t = Mode::Production)]
+   mode: Mode,
+
+   #[arg(long, value_delimiter = ',')]
+   conditions: Option<Vec<String>>,
+
+   #[arg(long, value_delimiter = ',')]
+   main_fields: Option<Vec<String>>,
+
+   #[arg(long, value_enum)]
+   platform: Option<Platform>,
+
+   #[arg(long, value_enum, default_value_t = Interop::Babel)]
+   interop: Interop,
+
+   #[arg(long, value_enum, default_value_t = Dirname::Keep)]
+   dirname: Dirname,
+
+   #[arg(long, value_enum, default_value_t = RequireResolve::Error)]
+   require_resolve: RequireResolve,
+
+   #[arg(long, value_enum, default_value_t = ComputedSpecifiers::Warn)]
+   computed_specifiers: ComputedSpecifiers,
+
+   #[arg(long = "const")]
+   consts: Vec<consts::Const>,
+
+   #[arg(long = "flag")]
+   flags: Vec<String>,
+
+   #[arg(long)]
+   trace: Option<String>,
+
+   #[arg(long)]
+   profile_modules: bool,
+
+   #[arg(long)]
+   size_report: bool,
+
+   #[arg(long, conflicts_with_all = ["check", "dry_run"])]
+   audit_determinism: bool,
+
+   #[arg(short, long = "format", value_enum)]
+   formats: Vec<Format>,
+
+   #[arg(short = 'x', long = "external")]
+   externals: Vec<String>,
+
+   #[arg(long)]
+   inline_dynamic_imports: bool,
+
+   #[arg(long)]
+   inline: bool,
+
+   #[arg(long)]
+   no_dce: bool,
+
+   #[arg(long)]
+   no_hygiene: bool,
+
+   #[arg(long)]
+   no_fixer: bool,
+
+   #[arg(long)]
+   mangle: bool,
+
+   #[arg(long, requires = "mangling")]
+   keep_names: bool,
+
+   #[arg(long, requires = "mangling", value_delimiter = ',')]
+   reserved: Vec<String>,
+
+   #[arg(long = "ignore")]
+   ignores: Vec<String>,
+
+   #[arg(long = "virtual", value_parser = parse_key_value)]
+   virtual_modules: Vec<(String, String)>,
+
+   #[arg(long = "virtual-json", value_parser = parse_key_value)]
+   virtual_json_modules: Vec<(String, String)>,
+
+   #[arg(long, default_value_t = String::from("React.createElement"))]
+   jsx_factory: String,
+
+   #[arg(long, default_value_t = String::from("React.Fragment"))]
+   jsx_fragment: String,
+
+   #[arg(long)]
+   jsx_import_source: Option<String>,
+
+   #[arg(long)]
+   amd_id: Option<String>,
+
+   #[arg(long = "amd-dependency", value_parser = parse_key_value)]
+   amd_dependencies: Vec<(String, String)>,
+
+   #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+   use_strict: bool,
+
+   #[arg(long)]
+   import_map: Option<String>,
+
+   #[arg(long, requires = "external_url")]
+   import_map_out: Option<String>,
+
+   #[arg(long)]
+   licenses_out: Option<String>,
+
+   #[arg(long = "deny-license")]
+   deny_licenses: Vec<String>,
+
+   #[arg(long)]
+   sbom: Option<String>,
+
+   #[arg(long)]
+   package_policy: Option<String>,
+
+   #[arg(long)]
+   external_url: Option<String>,
+
+   #[arg(long)]
+   stdin: bool,
+
+   #[arg(long, requires = "stdin")]
+   stdin_resolve_dir: Option<String>,
+
+   #[arg(long)]
+   reproducible: bool,
+
+   #[arg(long)]
+   preserve_symlinks: bool,
+
+   #[arg(long)]
+   lib: bool,
+
+   #[arg(long)]
+   dry_run: bool,
+
+   #[arg(long)]
+   no_eval: bool,
+
+   #[arg(long)]
+   client: bool,
+
+   #[arg(long)]
+   verify_output: bool,
+
+   #[arg(long)]
+   sourcemap_validate: bool,
+
+   #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+   write: bool,
+
+   #[arg(long, value_enum, default_value_t = Check::Warn)]
+   circular: Check,
+
+   #[arg(long, value_enum, default_value_t = Check::Error)]
+   missing_exports: Check,
+
+   #[arg(long, value_enum, default_value_t = Check::Error)]
+   duplicate_exports: Check,
+
+   #[arg(long, value_enum, default_value_t = Check::Warn)]
+   peer_dependencies: Check,
+
+   #[arg(long = "transform", value_parser = parse_key_value)]
+   transforms: Vec<(String, String)>,
+
+   #[arg(long)]
+   cache_dir: Option<String>,
+
+   #[arg(long)]
+   cache_url: Option<String>,
+
+   #[arg(short, long, env = "PLEASE_BUNDLE_JOBS")]
+   jobs: Option<NonZeroUsize>,
+
+   #[arg(long)]
+   allow_remote: bool,
+
+   #[arg(long, requires = "allow_remote")]
+   remote_lock: Option<String>,
+
+   #[arg(short, long = "input", value_parser = parse_key_value)]
+   named_inputs: Vec<(String, String)>,
+
+   #[arg(long)]
+   outdir: Option<String>,
+
+   #[arg(long, requires = "outdir")]
+   sourcemap: bool,
+
+   #[arg(long, requires = "outdir")]
+   provenance: bool,
+
+   #[arg(long, requires = "outdir", default_value_t = String::from("[name]"))]
+   entry_names: String,
+
+   #[arg(long, requires = "outdir")]
+   minified: bool,
+
+   #[arg(long)]
+   check: bool,
+
+   #[arg(long, requires = "outdir", value_enum, value_delimiter = ',')]
+   compress: Vec<compress::Compression>,
+
+   #[arg(long, requires = "outdir")]
+   html_snippet: Option<String>,
+
+   #[arg(long, requires = "outdir")]
+   public_dir: Option<String>,
+
    inputs: Vec<String>,
 }
 
-#[derive(Deserialize)]
-struct ExportConfig {
-    #[serde(default)]
-    import: Option<String>,
+/// Replaces every `@file` argument with the lines of that file, one
+/// argument per line, for command lines too long for the OS.
+fn expand_response_files(args: impl IntoIterator<Item = String>) -> Result<Vec<String>, Error> {
+    let mut expanded = vec![];
 
-    #[serde(default)]
-    default: Option<String>
+    for arg in args {
+        match arg.strip_prefix('@') {
+            Some(path) if !path.is_empty() => {
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|why| anyhow!("failed to read response file {path:?}: {why}"))?;
+                expanded.extend(contents.lines().filter(|line| !line.is_empty()).map(String::from));
+            },
+            _ => expanded.push(arg),
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Package directories listed in a manifest, either as a JSON array or one
+/// per line. Relative directories are taken from the manifest's directory.
+fn load_packages_manifest(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let contents = std::fs::read_to_string(path)?;
+
+    let dirs: Vec<String> = match serde_json::from_str(&contents) {
+        Ok(dirs) => dirs,
+        Err(_) if !contents.trim_start().starts_with('[') => contents.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(why) => bail!("failed to parse packages manifest {path:?}: {why}"),
+    };
+
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    Ok(dirs.into_iter().map(|dir| base_dir.join(dir)).collect())
+}
+
+/// What the build is for, which decides the export conditions packages are
+/// resolved with.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Development,
+    Production,
+}
+
+impl Mode {
+    /// `import` along with the mode's own condition, unless `overrides`
+    /// names the conditions to use in its place.
+    fn conditions(&self, overrides: &Option<Vec<String>>) -> Vec<String> {
+        let mode = match self {
+            Mode::Development => "development",
+            Mode::Production => "production",
+        };
+
+        let mut conditions = vec![String::from("import")];
+        match overrides {
+            Some(overrides) => conditions.extend(overrides.iter().cloned()),
+            None => conditions.push(String::from(mode)),
+        }
+        conditions
+    }
+}
+
+fn parse_key_value(arg: &str) -> Result<(String, String), Error> {
+    match arg.split_once('=') {
+        Some((key, value)) => Ok((key.to_string(), value.to_string())),
+        None => bail!("expected key=value, got {arg:?}"),
+    }
 }
 
 #[derive(Deserialize)]
 struct PackageJson {
     #[serde(default)]
     name: Option<String>,
-
-    #[serde(default)]
-    main: Option<String>,
-    #[serde(default)]
-    browser: Option<String>,
     #[serde(default)]
-    module: Option<String>,
+    version: Option<String>,
+    /// Written by some npm versions when installing.
+    #[serde(default, rename = "_integrity")]
+    integrity: Option<String>,
 
     #[serde(default)]
-    exports: Option<HashMap<String, ExportConfig>>,
+    exports: Option<serde_json::Value>,
+
+    /// Everything else, for looking up entry points by whichever main fields
+    /// are asked for; only string values count.
+    #[serde(flatten)]
+    fields: HashMap<String, serde_json::Value>,
 }
 
 /*#[derive(Deserialize)]
@@ -72,6 +409,7 @@ enum Browser {
     Obj(HashMap<String, StringOrBool>),
 }*/
 
+#[allow(dead_code)]
 #[derive(Deserialize, Clone)]
 #[serde(untagged)]
 enum StringOrBool {
@@ -80,7 +418,37 @@ enum StringOrBool {
 }
 
 
-fn load_package_entrypoint(path: PathBuf) -> Result<Vec<(String, FileName)>, Error> {
+/// The subpaths of an `exports` field with what each maps to: `exports` is
+/// either a map of subpaths, all starting with `.`, or what `.` maps to.
+fn export_targets(exports: &serde_json::Value) -> Vec<(String, &serde_json::Value)> {
+    match exports {
+        serde_json::Value::Object(map) if map.keys().all(|key| key.starts_with('.')) => {
+            map.iter().map(|(subpath, target)| (subpath.clone(), target)).collect()
+        },
+        _ => vec![(String::from("."), exports)],
+    }
+}
+
+/// Follows an export target through any conditions, taking the first key of
+/// each condition object, in the order it's written, that is active or is
+/// `default`, as Node does.
+fn resolve_conditions<'a>(target: &'a serde_json::Value, conditions: &[String]) -> Option<&'a str> {
+    match target {
+        serde_json::Value::String(path) => Some(path),
+        serde_json::Value::Array(targets) => targets.iter().find_map(|target| resolve_conditions(target, conditions)),
+        serde_json::Value::Object(map) => map.iter()
+            .filter(|(condition, _)| *condition == "default" || conditions.contains(condition))
+            .find_map(|(_, target)| resolve_conditions(target, conditions)),
+        _ => None,
+    }
+}
+
+fn load_package_entrypoint(
+    path: PathBuf,
+    preserve_symlinks: bool,
+    conditions: &[String],
+    main_fields: &[String],
+) -> Result<Vec<(String, FileName)>, Error> {
     let mut file = File::open(&path)?;
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
@@ -97,35 +465,33 @@ fn load_package_entrypoint(path: PathBuf) -> Result<Vec<(String, FileName)>, Err
     };
 
     if let Some(exports) = package_json.exports {
-        exports.iter()
-            .map(|(export_name, config)| {
-                let entrypoints = [
-                    config.import.as_ref(),
-                    config.default.as_ref(),
-                ];
-
-                if let Some(Some(entrypoint)) = entrypoints.iter().find(|x| x.is_some()) {
-                    let entrypoint_path = PathBuf::from(entrypoint);
-                    let full_entrypoint = package_dir.join(entrypoint_path).canonicalize().unwrap();
-
-                    let mut full_export_name = name.clone();
-                    full_export_name.push_str(&export_name[1..]);
-
-                    Ok((full_export_name, FileName::Real(full_entrypoint)))
-                } else {
-                    Err(anyhow!("no entrypoint is set, don't know how to load the package"))
-                }
+        let entrypoints = export_targets(&exports).into_iter()
+            .filter(|(subpath, _)| !subpath.contains('*'))
+            .filter_map(|(subpath, target)| {
+                let entrypoint = resolve_conditions(target, conditions)?;
+                Some((subpath, entrypoint))
+            })
+            .map(|(subpath, entrypoint)| {
+                let full_entrypoint = paths::resolve(&package_dir.join(entrypoint), preserve_symlinks)?;
+
+                let mut full_export_name = name.clone();
+                full_export_name.push_str(&subpath[1..]);
+
+                Ok((full_export_name, FileName::Real(full_entrypoint)))
             })
-            .collect::<Result<Vec<(String, FileName)>, Error>>()
+            .collect::<Result<Vec<(String, FileName)>, Error>>()?;
+
+        if entrypoints.is_empty() {
+            bail!("no export of {path:?} matches the conditions {conditions:?}");
+        }
+
+        Ok(entrypoints)
     } else {
-        let entrypoints = [
-            package_json.browser.as_ref(),
-            package_json.module.as_ref(),
-            package_json.main.as_ref(),
-        ];
-
-        if let Some(Some(entrypoint)) = entrypoints.iter().find(|x| x.is_some()) {
-            let full_entrypoint = package_dir.join(entrypoint).canonicalize()?;
+        let entrypoint = main_fields.iter()
+            .find_map(|field| package_json.fields.get(field).and_then(|value| value.as_str()));
+
+        if let Some(entrypoint) = entrypoint {
+            let full_entrypoint = paths::resolve(&package_dir.join(entrypoint), preserve_symlinks)?;
             Ok(vec![(name, FileName::Real(full_entrypoint))])
         } else {
             Err(anyhow!("no entrypoint is set, don't know how to load the package"))
@@ -134,15 +500,327 @@ fn load_package_entrypoint(path: PathBuf) -> Result<Vec<(String, FileName)>, Err
 }
 
 
+/// The directories of the packages any of `modules` were loaded from.
+fn bundled_packages(package_dirs: &[PathBuf], modules: &BTreeMap<String, analyze::ModuleInfo>, preserve_symlinks: bool) -> Result<Vec<PathBuf>, Error> {
+    let mut bundled = vec![];
+
+    for package_dir in package_dirs.iter().filter(|dir| dir.join("package.json").exists()) {
+        let resolved = paths::resolve(package_dir, preserve_symlinks)?;
+        if modules.keys().any(|file| Path::new(file).starts_with(&resolved)) {
+            bundled.push(resolved);
+        }
+    }
+
+    bundled.sort();
+    bundled.dedup();
+    Ok(bundled)
+}
+
+/// Expands any glob patterns among the inputs ourselves, as not every shell
+/// will. Matches are sorted so the entries don't depend on directory order.
+fn expand_inputs(inputs: &[String]) -> Result<Vec<PathBuf>, Error> {
+    let mut paths = vec![];
+
+    for input in inputs {
+        if input.contains(['*', '?', '[']) {
+            let mut matched = glob::glob(input)?.collect::<Result<Vec<PathBuf>, _>>()?;
+            if matched.is_empty() {
+                bail!("no files match {input:?}");
+            }
+
+            matched.sort();
+            paths.extend(matched);
+        } else {
+            paths.push(PathBuf::from(input));
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Entry names pick the output file names, so two entries sharing one is an
+/// error rather than one silently replacing the other.
+fn add_input(inputs: &mut HashMap<String, FileName>, name: String, path: FileName) -> Result<(), Error> {
+    if let Some(existing) = inputs.get(&name) {
+        bail!("entry name '{name}' is used by both {existing} and {path}, name them with --input name=path");
+    }
+
+    inputs.insert(name, path);
+    Ok(())
+}
+
+/// Writes an output file under the outdir, along with its source map,
+/// provenance and compressed copies when they're asked for.
+fn write_output(cm: &Lrc<SourceMap>, path: &Path, code: &str, srcmap: &[(BytePos, LineCol)], args: &Args, sink: &output::Sink, stale: &mut Vec<String>) -> Result<(), Error> {
+    if args.verify_output {
+        verify_output(&path.to_string_lossy(), code)?;
+    }
+
+    // Compressing first means a compressor that's missing or fails leaves
+    // nothing half written.
+    let compressed = args.compress.iter()
+        .map(|compression| {
+            let mut compressed_path = path.as_os_str().to_owned();
+            compressed_path.push(compression.extension());
+            Ok((PathBuf::from(compressed_path), compression.compress(code.as_bytes())?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    write_or_check(path, code.as_bytes(), args, sink, stale)?;
+
+    for (compressed_path, contents) in &compressed {
+        write_or_check(compressed_path, contents, args, sink, stale)?;
+    }
+
+    if args.sourcemap {
+        let mut map_path = path.as_os_str().to_owned();
+        map_path.push(".map");
+        let map = source_map(cm, srcmap, Path::new(&map_path), code, args)?;
+        write_or_check(Path::new(&map_path), &map, args, sink, stale)?;
+    }
+
+    if args.provenance {
+        let mut provenance_path = path.as_os_str().to_owned();
+        provenance_path.push(".provenance.json");
+        let regions = provenance::regions(cm, code, srcmap)?;
+        write_or_check(Path::new(&provenance_path), &serde_json::to_vec_pretty(&regions)?, args, sink, stale)?;
+    }
+
+    Ok(())
+}
+
+/// Writes `contents` to `path` or, with --check, compares them with what's
+/// already there and notes how the file is out of date in `stale`. With
+/// --write=false only the size is reported.
+fn write_or_check(path: &Path, contents: &[u8], args: &Args, sink: &output::Sink, stale: &mut Vec<String>) -> Result<(), Error> {
+    if !args.write {
+        eprintln!("would write {} ({} bytes)", path.display(), contents.len());
+        return Ok(());
+    }
+
+    if args.check {
+        match std::fs::read(path) {
+            Ok(existing) if existing == contents => {},
+            Ok(existing) => {
+                let line = output::first_different_line(&String::from_utf8_lossy(&existing), &String::from_utf8_lossy(contents));
+                stale.push(format!("{} differs from line {line}", path.display()));
+            },
+            Err(_) => stale.push(format!("{} doesn't exist", path.display())),
+        }
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    sink.write(path, contents)?;
+    Ok(())
+}
+
+/// Parses emitted code back in, to catch code generation producing
+/// something that isn't JavaScript before anyone tries to run it.
+fn verify_output(name: &str, code: &str) -> Result<(), Error> {
+    let cm: Lrc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(FileName::Custom(name.to_string()), code.to_string());
+
+    match parse_file_as_module(&fm, Language::JavaScript.syntax(), EsVersion::Es2020, None, &mut vec![]) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            let loc = cm.lookup_char_pos(err.span().lo);
+            bail!("{name}:{}:{} doesn't parse back in: {}", loc.line, loc.col_display + 1, err.kind().msg())
+        },
+    }
+}
+
+fn emit(cm: &Lrc<SourceMap>, module: &Module, minify: bool) -> Result<(String, Vec<(BytePos, LineCol)>), Error> {
+    let mut srcmap = vec![];
+    let mut buf = vec![];
+
+    {
+        let wr = JsWriter::new(cm.clone(), "\n", &mut buf, Some(&mut srcmap));
+        let mut emitter = Emitter {
+            cfg: swc_ecma_codegen::Config {
+                minify,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: None,
+            wr: Box::new(wr) as Box<dyn WriteJs>,
+        };
+
+        emitter.emit_module(module)?;
+    }
+
+    Ok((String::from_utf8_lossy(&buf).to_string(), srcmap))
+}
+
+/// The source map of `code` to be written to `path`, checked over first
+/// with --sourcemap-validate. Reproducible maps name their sources relative
+/// to the map rather than by absolute host paths.
+fn source_map(cm: &Lrc<SourceMap>, srcmap: &[(BytePos, LineCol)], path: &Path, code: &str, args: &Args) -> Result<Vec<u8>, Error> {
+    let relative_to = if args.reproducible {
+        let dir = match path.parent() {
+            Some(dir) if dir != Path::new("") => paths::canonicalize(dir)?,
+            _ => paths::current_dir()?,
+        };
+        Some(dir)
+    } else {
+        None
+    };
+
+    if args.sourcemap_validate {
+        report_source_map_problems(path, output::mapping_problems(cm, srcmap))?;
+    }
+
+    let config = output::SourceMapConfig { relative_to };
+    let map = cm.build_source_map_with_config(srcmap, None, &config);
+    let mut buf = vec![];
+    map.to_writer(&mut buf)?;
+
+    if args.sourcemap_validate {
+        report_source_map_problems(path, output::source_map_problems(cm, &config, code, &map, &buf))?;
+    }
+
+    Ok(buf)
+}
+
+fn report_source_map_problems(path: &Path, problems: Vec<String>) -> Result<(), Error> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    for problem in &problems {
+        eprintln!("sourcemap: {}: {problem}", path.display());
+    }
+    bail!("found {} problem(s) with the source map {}", problems.len(), path.display())
+}
+
 fn main() -> Result<(), Error> {
+    let cli = expand_response_files(std::env::args())?;
+
+    match config::take_config(&cli) {
+        (None, _) => build(Args::parse_from(cli)),
+        (Some(path), cli) => {
+            let builds: Vec<Args> = config::builds(Path::new(&path), &cli)?.into_iter()
+                .map(Args::parse_from)
+                .collect();
+
+            // Builds share nothing but the caches on disk, so they're shared
+            // out between as many threads as --jobs allows. It's one setting
+            // for the whole run, so builds can't each ask for their own.
+            let asked: BTreeSet<Option<NonZeroUsize>> = builds.iter().map(|args| args.jobs).collect();
+            if asked.len() > 1 {
+                bail!("the builds in {path} set different --jobs, set it once for all of them");
+            }
+            let jobs = builds.first().map_or(1, jobs).min(builds.len());
+            let queue = Mutex::new(builds.into_iter());
+
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = (0..jobs)
+                    .map(|_| scope.spawn(|| loop {
+                        let next = queue.lock().unwrap().next();
+                        match next {
+                            Some(args) => build(args)?,
+                            None => return Ok::<(), Error>(()),
+                        }
+                    }))
+                    .collect();
+
+                workers.into_iter().try_for_each(|worker| match worker.join() {
+                    Ok(result) => result,
+                    Err(panic) => std::panic::resume_unwind(panic),
+                })
+            })
+        },
+    }
+}
+
+/// How many threads --jobs allows, as many as the machine has by default.
+fn jobs(args: &Args) -> usize {
+    args.jobs
+        .or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get)
+}
+
+fn build(mut args: Args) -> Result<(), Error> {
+    // Without --format, builds are whatever their platform loads, which is
+    // an ES module unless it says otherwise.
+    if args.formats.is_empty() {
+        args.formats.push(args.platform.and_then(|platform| platform.format()).unwrap_or(Format::Esm));
+    }
+
+    let trace = Lrc::new(trace::Trace::new(args.trace.is_some() || args.profile_modules));
+    let trace_path = args.trace.clone();
+    let profile_modules = args.profile_modules;
+
+    let result = if args.audit_determinism {
+        audit_determinism(&args, &trace)
+    } else {
+        bundle(&args, &trace, &output::Sink::default())
+    };
+
+    // A trace is most wanted when the build went wrong, so it's written
+    // either way.
+    if let Some(path) = trace_path {
+        trace.write(Path::new(&path))?;
+    }
+    if profile_modules {
+        for line in trace.module_report(10) {
+            eprintln!("profile: {line}");
+        }
+    }
+    result
+}
+
+/// Builds twice and compares everything the two builds would write before
+/// writing out the first. The second build runs on a thread of its own, so
+/// every HashMap in it is seeded afresh and anything leaning on their order
+/// shows up as a difference.
+fn audit_determinism(args: &Args, trace: &Lrc<trace::Trace>) -> Result<(), Error> {
+    let first = output::Sink::holding();
+    bundle(args, trace, &first)?;
+
+    let second = std::thread::scope(|scope| {
+        scope.spawn(|| {
+            let second = output::Sink::holding();
+            bundle(args, &Lrc::new(trace::Trace::new(false)), &second).map(|()| second)
+        })
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })?;
+
+    if let Some(divergence) = first.first_divergence(&second) {
+        eprintln!("determinism: {divergence}");
+        bail!("two builds of the same inputs differ");
+    }
+
+    first.release()?;
+    Ok(())
+}
 
-    let args = Args::parse();
+fn bundle(args: &Args, trace: &Lrc<trace::Trace>, sink: &output::Sink) -> Result<(), Error> {
 
-    let packages: HashMap<String, FileName> = args.packages.iter()
-        .map(|package_path| Path::new(package_path).join("package.json"))
+    let mut package_dirs: Vec<PathBuf> = args.packages.iter().map(PathBuf::from).collect();
+    for manifest in &args.packages_manifests {
+        package_dirs.extend(load_packages_manifest(Path::new(manifest))?);
+    }
+
+    let mut conditions = args.mode.conditions(&args.conditions);
+    if let (Some(platform), None) = (args.platform, &args.conditions) {
+        conditions.extend(platform.conditions().iter().map(|condition| condition.to_string()));
+    }
+
+    let main_fields: Vec<String> = match (&args.main_fields, args.platform) {
+        (Some(main_fields), _) => main_fields.clone(),
+        (None, Some(platform)) => platform.main_fields().iter().map(|field| field.to_string()).collect(),
+        (None, None) => platform::DEFAULT_MAIN_FIELDS.iter().map(|field| field.to_string()).collect(),
+    };
+
+    let packages: HashMap<String, FileName> = package_dirs.iter()
+        .map(|package_path| package_path.join("package.json"))
         .filter(|package_path| package_path.exists())
         .try_fold(HashMap::new(), |mut map, path| {
-            for (name, entrypoint_path) in load_package_entrypoint(path)? {
+            for (name, entrypoint_path) in load_package_entrypoint(path, args.preserve_symlinks, &conditions, &main_fields)? {
                 map.insert(name, entrypoint_path);
             }
             Ok::<HashMap<String, FileName>, Error>(map)
@@ -150,79 +828,562 @@ fn main() -> Result<(), Error> {
 
     eprintln!("packages: {:#?}", packages);
 
-    let inputs:  Result<HashMap<String, FileName>, Error> = args.inputs.iter()
-        .map(|path| Path::new(path).to_path_buf())
-        .filter(|path| path.exists())
-        .try_fold(HashMap::new(), |mut map, path| {
-            if let Some(file_name) = path.file_name() {
-                if let Some(file_name_string) = file_name.to_str() {
-                    map.insert(String::from(file_name_string), FileName::Real(path));
-                    Ok(map)
-                } else {
-                    Err(anyhow!("os string didn't convert to a &str"))
-                }
-            } else {
-                Err(anyhow!("can't get file name for {:?}", path))
+    if let Some(path) = &args.lockfile {
+        let lockfile = lockfile::Lockfile::load(Path::new(path))?;
+
+        let mut drifted = vec![];
+        for package_dir in package_dirs.iter().filter(|dir| dir.join("package.json").exists()) {
+            let package_path = package_dir.join("package.json");
+            let package_json: PackageJson = serde_json::from_str(&std::fs::read_to_string(&package_path)?)?;
+            let name = match &package_json.name {
+                None => bail!("no name for js package at {package_path:?}"),
+                Some(name) => name,
+            };
+
+            match lockfile.check(name, package_json.version.as_deref(), package_json.integrity.as_deref()) {
+                Some(message) => drifted.push(message),
+                // The files on disk are checked against the tarball the
+                // lockfile pins, so editing them after install is caught.
+                None => if let (Some(tarballs), Some(version)) = (&args.package_tarballs, &package_json.version) {
+                    match lockfile.integrity(name, version) {
+                        Some(integrity) => {
+                            let tarball = integrity::tarball_path(Path::new(tarballs), name, version);
+                            drifted.extend(integrity::verify(&format!("{name}@{version}"), package_dir, &tarball, integrity)?);
+                        },
+                        None => drifted.push(format!("{name}@{version} has no integrity in the lockfile to verify its tarball against")),
+                    }
+                },
             }
-        });
+        }
+
+        for message in &drifted {
+            eprintln!("lockfile: {message}");
+        }
+
+        if !drifted.is_empty() {
+            bail!("{} package(s) don't match the lockfile", drifted.len());
+        }
+    }
+
+    let import_map = match &args.import_map {
+        Some(path) => Some(ImportMap::load(Path::new(path))?),
+        None => None,
+    };
+
+    let mut externals = args.externals.clone();
+    if let Some(platform) = args.platform {
+        externals.extend(platform.externals());
+    }
+    if let Some(import_map) = &import_map {
+        externals.extend(import_map.externals());
+    }
+
+    let mut inputs: HashMap<String, FileName> = HashMap::new();
+    for path in expand_inputs(&args.inputs)?.into_iter().filter(|path| path.exists()) {
+        let name = match path.file_stem().map(|file_stem| file_stem.to_str()) {
+            Some(Some(name)) => String::from(name),
+            Some(None) => bail!("os string didn't convert to a &str"),
+            None => bail!("can't get file name for {:?}", path),
+        };
+        add_input(&mut inputs, name, FileName::Real(paths::resolve(&path, args.preserve_symlinks)?))?;
+    }
+    for (name, path) in &args.named_inputs {
+        add_input(&mut inputs, name.clone(), FileName::Real(paths::resolve(Path::new(path), args.preserve_symlinks)?))?;
+    }
+
+    let stdin = if args.stdin {
+        let resolve_dir = match &args.stdin_resolve_dir {
+            Some(dir) => paths::canonicalize(Path::new(dir))?,
+            None => paths::current_dir()?,
+        };
+
+        let mut source = String::new();
+        std::io::stdin().read_to_string(&mut source)?;
+
+        Some((resolve_dir.join("<stdin>.js"), source))
+    } else {
+        None
+    };
+
+    if let Some((path, _)) = &stdin {
+        add_input(&mut inputs, String::from("stdin"), FileName::Real(path.clone()))?;
+    }
 
     eprintln!("inputs: {:#?}", inputs);
 
     let globals = Globals::default();
     let cm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
-    let mut bundler = Bundler::new(
-        &globals,
-        cm.clone(),
-        Loader { cm: cm.clone() },
-        Resolver { packages: packages },
-        swc_bundler::Config {
-            require: false,
-            disable_inliner: true, // !inline,
-            external_modules: Default::default(),
-            disable_fixer: false, // minify,
-            disable_hygiene: false, // minify,
-            disable_dce: false,
-            module: Default::default(),
+    let virtual_modules = virtuals::sources(&args.virtual_modules, &args.virtual_json_modules)?;
+
+    let loader = Loader {
+        cm: cm.clone(),
+        interop: args.interop,
+        stdin,
+        modules: Default::default(),
+        loaded: Default::default(),
+        const_enums: Default::default(),
+        transforms: transform::Transforms::new(args.transforms.clone(), args.cache_dir.as_ref().map(PathBuf::from), args.cache_url.clone()),
+        package_types: Default::default(),
+        virtual_modules: virtual_modules.clone(),
+        jsx: jsx::Options {
+            factory: args.jsx_factory.clone(),
+            fragment: args.jsx_fragment.clone(),
+            import_source: args.jsx_import_source.clone(),
         },
-        Box::new(Hook{}),
-    );
+        inline_dynamic_imports: args.inline_dynamic_imports,
+        dirname: args.dirname,
+        require_resolve: args.require_resolve,
+        no_runtime_require: args.formats.iter().find_map(|format| match format {
+            Format::Cjs => None,
+            Format::Esm if args.platform.is_some_and(|platform| platform.has_create_require()) => None,
+            Format::Esm => Some(String::from("ES modules only have a require with a --platform that makes one, such as node")),
+            Format::System => Some(String::from("System.register modules have no require")),
+            Format::Amd => Some(String::from("AMD modules have no require.resolve")),
+        }),
+        computed_specifiers: args.computed_specifiers,
+        flags: args.flags.clone(),
+        trace: trace.clone(),
+        externals: externals.clone(),
+        remote: remote::Remote::new(args.cache_dir.as_ref().map(PathBuf::from), args.remote_lock.as_ref().map(PathBuf::from))?,
+    };
+    let resolver = Resolver {
+        packages,
+        import_map: import_map.clone(),
+        preserve_symlinks: args.preserve_symlinks,
+        allow_remote: args.allow_remote,
+        virtual_modules,
+        ignores: args.ignores.iter()
+            .map(|pattern| glob::Pattern::new(pattern).map_err(|why| anyhow!("bad --ignore pattern {pattern:?}: {why}")))
+            .collect::<Result<_, Error>>()?,
+        graph: ModuleGraph::default(),
+        trace: trace.clone(),
+    };
+
+    let sources: HashMap<String, PathBuf> = inputs.iter()
+        .filter_map(|(name, file_name)| match file_name {
+            FileName::Real(path) => Some((name.clone(), path.clone())),
+            _ => None,
+        })
+        .collect();
+
+    // Entries are bundled side by side, as many at once as --jobs allows,
+    // each by a bundler and a deduper of its own, so module ids, generated
+    // names and which of a set of identical files gets bundled never depend
+    // on which other entries happen to be part of the build. They share the
+    // loader, so a module they have in common is only read, transformed and
+    // parsed once.
+    let mut entries: Vec<(String, FileName)> = inputs.into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let entry_files: HashMap<String, FileName> = entries.iter().cloned().collect();
+
+    let workers = jobs(args).min(entries.len());
+    let queue = Mutex::new(entries.into_iter().enumerate());
+
+    let mut bundled = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..workers)
+            .map(|_| scope.spawn(|| {
+                let mut bundled = vec![];
+                loop {
+                    let next = queue.lock().unwrap().next();
+                    let (index, (name, file_name)) = match next {
+                        Some(next) => next,
+                        None => return bundled,
+                    };
+
+                    let resolver = EntryResolver { resolver: &resolver, deduper: Default::default() };
+                    let mut bundler = Bundler::new(
+                        &globals,
+                        cm.clone(),
+                        &loader,
+                        &resolver,
+                        swc_bundler::Config {
+                            require: false,
+                            disable_inliner: !args.inline,
+                            external_modules: externals.iter().map(|external| external.as_str().into()).collect(),
+                            disable_fixer: args.no_fixer,
+                            disable_hygiene: args.no_hygiene,
+                            disable_dce: args.no_dce,
+                            module: Default::default(),
+                        },
+                        Box::new(Hook{}),
+                    );
+
+                    let _bundle = trace.span("bundle", &name);
+                    match bundler.bundle(HashMap::from([(name, file_name)])) {
+                        Err(why) => panic!("failed to bundle: {why:?}"),
+                        Ok(bundles) => bundled.push((index, bundles)),
+                    }
+                }
+            }))
+            .collect();
+
+        workers.into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect::<Vec<_>>()
+    });
+    bundled.sort_by_key(|(index, _)| *index);
+
+    let mut outputs = vec![];
+    for bundle in bundled.into_iter().flat_map(|(_, bundles)| bundles) {
+        match bundle.kind {
+            BundleKind::Named { name } | BundleKind::Lib { name } => outputs.push((name, bundle.module)),
+            BundleKind::Dynamic => bail!("dynamically imported bundles aren't supported"),
+        }
+    }
+
+    let mut stale = vec![];
+
+    if !args.dry_run {
+        if let Some((path, contents)) = loader.remote.lock_file()? {
+            write_or_check(path, &contents, args, sink, &mut stale)?;
+        }
+    }
+
+    // The platform's constants come first so that --const wins over them.
+    let consts: Vec<consts::Const> = args.platform.map(|platform| platform.consts()).unwrap_or_default().into_iter()
+        .chain(args.consts.iter().cloned())
+        .collect();
+
+    outputs = outputs.into_iter()
+        .map(|(name, module)| {
+            let shadowed = analyze::shadowed_exports(&loader.modules.borrow(), &resolver.graph, &entry_files[&name].to_string());
+            let mut module = analyze::drop_shadowed_exports(&cm, module, &shadowed);
+            enums::inline_imported(&mut module, &loader.const_enums.borrow());
+            (name, module)
+        })
+        .map(|(name, module)| Ok((name, consts::inline(&globals, &cm, module, &consts)?)))
+        .collect::<Result<_, Error>>()?;
+
+    let mangle_options = mangle::Options { keep_names: args.keep_names, reserved: args.reserved.clone() };
+    if args.mangle {
+        outputs = outputs.into_iter()
+            .map(|(name, module)| (name, mangle::mangle(&globals, module, &mangle_options)))
+            .collect();
+    }
+
+    if args.circular != Check::Ignore {
+        let cycles = resolver.graph.cycles();
+
+        for cycle in &cycles {
+            eprintln!("circular import: {}", cycle.join(" -> "));
+        }
+
+        if args.circular == Check::Error && !cycles.is_empty() {
+            bail!("found {} import cycle(s)", cycles.len());
+        }
+    }
+
+    if args.missing_exports != Check::Ignore {
+        let missing = analyze::missing_exports(&loader.modules.borrow(), &resolver.graph);
+
+        for message in &missing {
+            eprintln!("missing export: {message}");
+        }
+
+        if args.missing_exports == Check::Error && !missing.is_empty() {
+            bail!("found {} import(s) of missing exports", missing.len());
+        }
+    }
+
+    if args.duplicate_exports != Check::Ignore {
+        let mut collisions = vec![];
+        for name in outputs.iter().map(|(name, _)| name) {
+            collisions.extend(analyze::export_collisions(&loader.modules.borrow(), &resolver.graph, &entry_files[name].to_string())
+                .into_iter()
+                .map(|collision| format!("{name}: {collision}")));
+        }
+
+        for message in &collisions {
+            eprintln!("duplicate export: {message}");
+        }
+
+        if args.duplicate_exports == Check::Error && !collisions.is_empty() {
+            bail!("found {} name(s) exported by more than one module", collisions.len());
+        }
+    }
+
+    // Modules marked "use server" only run on the server, so a client
+    // bundle that takes one with it is wrong however it came to.
+    if args.client {
+        let mut reached = vec![];
+        for name in outputs.iter().map(|(name, _)| name) {
+            reached.extend(analyze::server_modules(&loader.modules.borrow(), &resolver.graph, &entry_files[name].to_string())
+                .into_iter()
+                .map(|chain| format!("{name}: {}", chain.join(" -> "))));
+        }
+
+        for message in &reached {
+            eprintln!("use server: {message}");
+        }
+
+        if !reached.is_empty() {
+            bail!("found {} \"use server\" module(s) in the client bundle", reached.len());
+        }
+    }
+
+    if args.no_eval {
+        let mut uses = vec![];
+        for (name, module) in &outputs {
+            uses.extend(csp::eval_uses(&cm, module).into_iter().map(|found| format!("{name}: {found}")));
+        }
+
+        for message in &uses {
+            eprintln!("eval: {message}");
+        }
+
+        if !uses.is_empty() {
+            bail!("found {} use(s) of eval or code in strings", uses.len());
+        }
+    }
+
+    // A library's bundle stands in for its entry, so it has to export
+    // exactly what the entry does.
+    if args.lib {
+        let mut changes = vec![];
+        for (name, module) in &outputs {
+            let entry = entry_files[name].to_string();
+            let bundle = analyze::module_info(&cm, module);
+            changes.extend(analyze::export_changes(&loader.modules.borrow(), &resolver.graph, &entry, &bundle)
+                .into_iter()
+                .map(|change| format!("{name}: {change}")));
+        }
+
+        for message in &changes {
+            eprintln!("lib: {message}");
+        }
 
-    let modules = match bundler.bundle(inputs?) {
-        Err(why) => panic!("failed to bundle: {why:?}"),
-        Ok(modules) => modules,
+        if !changes.is_empty() {
+            bail!("found {} change(s) to the entries' exports", changes.len());
+        }
+    }
+
+    let package_policy = match &args.package_policy {
+        Some(path) => Some(policy::Policy::load(Path::new(path))?),
+        None => None,
     };
 
-    assert!(modules.len() == 1, "we only expect one module to exist not: {}", modules.len());
+    let bundled = bundled_packages(&package_dirs, &loader.modules.borrow(), args.preserve_symlinks)?;
 
-    let mut srcmap = vec![];
-    let code = {
-        let mut buf = vec![];
-
-        {
-            let wr = JsWriter::new(cm.clone(), "\n", &mut buf, Some(&mut srcmap));
-            let mut emitter = Emitter {
-                cfg: swc_ecma_codegen::Config {
-                    minify: false,
-                    ..Default::default()
-                },
-                cm: cm.clone(),
-                comments: None,
-                wr: Box::new(wr) as Box<dyn WriteJs>,
+    let licenses = bundled.iter()
+        .map(|dir| licenses::PackageLicense::read(dir))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // Naming the imports that pulled a package in tells whoever has to
+    // get rid of it where to start.
+    if let Some(package_policy) = &package_policy {
+        let mut violations = vec![];
+        for (dir, package) in bundled.iter().zip(&licenses) {
+            let violation = match package_policy.violation(&package.name, package.version.as_deref())? {
+                Some(violation) => violation,
+                None => continue,
             };
 
-            emitter.emit_module(&modules[0].module).unwrap();
+            let mut chain: Vec<String> = vec![];
+            for (name, _) in &outputs {
+                let entry = entry_files[name].to_string();
+                for file in loader.modules.borrow().keys().filter(|file| Path::new(file).starts_with(dir)) {
+                    match resolver.graph.chain(&entry, file) {
+                        Some(found) if chain.is_empty() || found.len() < chain.len() => chain = found,
+                        _ => {},
+                    }
+                }
+            }
+
+            violations.push(format!("{} {violation}: {}", package.id(), chain.join(" -> ")));
+        }
+
+        for message in &violations {
+            eprintln!("policy: {message}");
         }
 
-        String::from_utf8_lossy(&buf).to_string()
+        if !violations.is_empty() {
+            bail!("found {} package(s) the policy doesn't allow", violations.len());
+        }
+    }
+
+    if args.peer_dependencies != Check::Ignore {
+        let mismatches = peers::mismatches(&bundled)?;
+
+        for message in &mismatches {
+            eprintln!("peer dependency: {message}");
+        }
+
+        if args.peer_dependencies == Check::Error && !mismatches.is_empty() {
+            bail!("found {} peer dependency mismatch(es)", mismatches.len());
+        }
+    }
+
+    let denied: Vec<&licenses::PackageLicense> = licenses.iter()
+        .filter(|license| license.is_denied(&args.deny_licenses))
+        .collect();
+
+    for license in &denied {
+        eprintln!("license: {} is licensed under {}, which is denied", license.id(), license.license.as_deref().unwrap_or_default());
+    }
+
+    if !denied.is_empty() {
+        bail!("found {} package(s) under denied licenses", denied.len());
+    }
+
+    // Everything has been resolved, parsed and checked by now, a dry run
+    // stops short of writing any of it.
+    if args.dry_run {
+        eprintln!("dry run: {} entries bundled, nothing written", outputs.len());
+        return Ok(());
+    }
+
+    if let Some(path) = &args.licenses_out {
+        write_or_check(Path::new(path), &serde_json::to_vec_pretty(&licenses)?, args, sink, &mut stale)?;
+    }
+
+    if let Some(path) = &args.sbom {
+        let bom = sbom::cyclonedx(&bundled, &loader.modules.borrow())?;
+        write_or_check(Path::new(path), &serde_json::to_vec_pretty(&bom)?, args, sink, &mut stale)?;
+    }
+
+    if let (Some(path), Some(url_pattern)) = (&args.import_map_out, &args.external_url) {
+        let modules: Vec<&Module> = outputs.iter().map(|(_, module)| module).collect();
+        let contents = import_map::externals_import_map(&modules, &externals, url_pattern, import_map.as_ref())?;
+        write_or_check(Path::new(path), &contents, args, sink, &mut stale)?;
+    }
+
+    let format_options = format::Options {
+        amd_id: args.amd_id.clone(),
+        amd_dependencies: args.amd_dependencies.iter().cloned().collect(),
+        strict: args.use_strict,
+        create_require: args.platform.is_some_and(|platform| platform.has_create_require()),
+        import_meta_dirname: args.dirname == Dirname::ImportMeta,
     };
 
-    println!("{}", code);
+    // What a module costs is what it adds to the minified bundle, which
+    // can be far from the size of its source.
+    if args.size_report {
+        let mut sizes: BTreeMap<(String, Option<String>), usize> = BTreeMap::new();
+        for (_, module) in &outputs {
+            let minified = mangle::mangle(&globals, module.clone(), &mangle_options);
+            let converted = format::convert(&globals, minified, args.formats[0], &format_options)?;
+            let (code, srcmap) = emit(&cm, &converted, true)?;
+
+            let regions = provenance::regions(&cm, &code, &srcmap)?;
+            let attributed: usize = regions.iter().map(|region| region.end - region.start).sum();
+            for region in regions {
+                *sizes.entry((region.file, region.package)).or_default() += region.end - region.start;
+            }
+            *sizes.entry((String::from("<generated>"), None)).or_default() += code.len() - attributed;
+        }
+
+        let mut sizes: Vec<_> = sizes.into_iter().filter(|(_, bytes)| *bytes > 0).collect();
+        sizes.sort_by(|(a_file, a), (b_file, b)| b.cmp(a).then(a_file.cmp(b_file)));
+        for ((file, package), bytes) in sizes {
+            match package {
+                Some(package) => eprintln!("size: {bytes} {file} ({package})"),
+                None => eprintln!("size: {bytes} {file}"),
+            }
+        }
+    }
+
+    if let Some(outdir) = &args.outdir {
+        let outbase = output::common_dir(sources.values().map(|path| path.as_path()));
+
+        if args.formats.len() > 1 && !args.entry_names.contains("[format]") {
+            bail!("--entry-names needs [format] to write more than one format");
+        }
+
+        let mut scripts = vec![];
+
+        // Every format is emitted from the same bundled modules, so the graph
+        // is only loaded and linked once however many are asked for.
+        for (name, module) in outputs {
+            // The minified copy is emitted next to the readable one, from
+            // the same bundle with its names mangled.
+            let minified = args.minified.then(|| mangle::mangle(&globals, module.clone(), &mangle_options));
+
+            for format in &args.formats {
+                let _emit = trace.span("emit", format!("{name} ({})", format.name()));
+                let template = args.entry_names.replace("[format]", format.name());
+                let source = sources.get(&name).map(|path| path.as_path());
+
+                let converted = format::convert(&globals, module.clone(), *format, &format_options)?;
+                let (code, srcmap) = emit(&cm, &converted, false)?;
+
+                let entry_path = output::entry_path(&template, &name, source, &outbase, &code);
+                scripts.push((*format, paths::to_slash(&entry_path)));
+                write_output(&cm, &Path::new(outdir).join(&entry_path), &code, &srcmap, args, sink, &mut stale)?;
+
+                if let Some(minified) = &minified {
+                    let converted = format::convert(&globals, minified.clone(), *format, &format_options)?;
+                    let (code, srcmap) = emit(&cm, &converted, true)?;
+
+                    let min_path = output::minified_path(&entry_path);
+                    write_output(&cm, &Path::new(outdir).join(min_path), &code, &srcmap, args, sink, &mut stale)?;
+                }
+            }
+        }
+
+        if let Some(path) = &args.html_snippet {
+            write_or_check(Path::new(path), output::script_tags(&scripts).as_bytes(), args, sink, &mut stale)?;
+        }
+
+        // Files such as favicons and robots.txt are copied over as they are,
+        // but not over an entry.
+        if let Some(public_dir) = &args.public_dir {
+            for file in output::public_files(Path::new(public_dir))? {
+                if scripts.iter().any(|(_, script)| Path::new(script) == file) {
+                    bail!("{} in {public_dir} would replace the entry written there", file.display());
+                }
+                write_or_check(&Path::new(outdir).join(&file), &std::fs::read(Path::new(public_dir).join(&file))?, args, sink, &mut stale)?;
+            }
+        }
+    } else {
+        if outputs.len() != 1 {
+            bail!("{} entries were bundled, pass --outdir to write more than one", outputs.len());
+        }
+
+        let format = match args.formats.as_slice() {
+            [format] => *format,
+            _ => bail!("{} formats were asked for, pass --outdir to write more than one", args.formats.len()),
+        };
+
+        let (name, module) = outputs.remove(0);
+        let _emit = trace.span("emit", name);
+        let module = format::convert(&globals, module, format, &format_options)?;
+        let (code, srcmap) = emit(&cm, &module, false)?;
+
+        if args.verify_output {
+            verify_output("<stdout>", &code)?;
+        }
+
+        if !args.write {
+            eprintln!("would write <stdout> ({} bytes)", code.len() + 1);
+            return Ok(());
+        }
+
+        // With `--map -` the map takes stdout, pushing the code out to
+        // --output, and --envelope puts both in one JSON object.
+        if args.envelope {
+            let map = source_map(&cm, &srcmap, &PathBuf::from(format!("{}.map", args.output)), &code, args)?;
+            let map: serde_json::Value = serde_json::from_slice(&map)?;
+            sink.print(&serde_json::json!({ "code": code, "map": map }).to_string());
+        } else if args.map.as_deref() == Some("-") {
+            let map = source_map(&cm, &srcmap, &PathBuf::from(format!("{}.map", args.output)), &code, args)?;
+            write_or_check(Path::new(&args.output), format!("{code}\n").as_bytes(), args, sink, &mut stale)?;
+            sink.print(&String::from_utf8(map)?);
+        } else {
+            sink.print(&code);
+
+            if let Some(map_path) = &args.map {
+                let map = source_map(&cm, &srcmap, Path::new(map_path), &code, args)?;
+                write_or_check(Path::new(map_path), &map, args, sink, &mut stale)?;
+            }
+        }
+    }
 
-    if let Some(map_path) = args.map {
-        let srcmap = cm.build_source_map(&srcmap);
-        let srcmap_file = File::create(map_path).unwrap();
-        let srcmap_wr = BufWriter::new(srcmap_file);
-        srcmap.to_writer(srcmap_wr).unwrap();
+    for message in &stale {
+        eprintln!("check: {message}");
+    }
+
+    if !stale.is_empty() {
+        bail!("found {} output(s) that don't match the files on disk", stale.len());
     }
 
     Ok(())
@@ -233,20 +1394,99 @@ fn main() -> Result<(), Error> {
 
 pub struct Loader {
     pub cm: Lrc<SourceMap>,
+    pub interop: Interop,
+    /// Entry read from stdin, loaded as if it lived at the given path.
+    pub stdin: Option<(PathBuf, String)>,
+    /// What every loaded module imports and exports, by file name.
+    pub modules: Lock<BTreeMap<String, analyze::ModuleInfo>>,
+    /// Every module loaded so far, shared by the bundlers of all entries.
+    pub loaded: Lock<HashMap<FileName, (Lrc<SourceFile>, Module)>>,
+    /// The const enums of every TypeScript module loaded so far.
+    pub const_enums: Lock<enums::ConstEnums>,
+    pub transforms: transform::Transforms,
+    pub package_types: kind::PackageTypes,
+    pub virtual_modules: HashMap<String, String>,
+    pub jsx: jsx::Options,
+    pub inline_dynamic_imports: bool,
+    pub dirname: Dirname,
+    pub require_resolve: RequireResolve,
+    /// Why the output has no `require` of its own for
+    /// --require-resolve=external to hand `require.resolve` to, if it hasn't.
+    pub no_runtime_require: Option<String>,
+    pub computed_specifiers: ComputedSpecifiers,
+    /// Flags the `// #if` pragmas of real files are checked against.
+    pub flags: Vec<String>,
+    pub trace: Lrc<trace::Trace>,
+    pub externals: Vec<String>,
+    pub remote: remote::Remote,
+}
+
+impl Loader {
+    /// An empty object as the default export, plus `undefined` for every
+    /// other name the modules loaded so far import from `specifier`, so
+    /// using them doesn't throw.
+    fn ignored_source(&self, specifier: &str) -> String {
+        let names: BTreeSet<String> = self.modules.borrow().values()
+            .flat_map(|info| info.imports.iter())
+            .filter(|import| import.specifier == specifier && import.name != "default")
+            .filter(|import| interop::is_identifier_name(&import.name))
+            .map(|import| import.name.clone())
+            .collect();
+
+        let mut src = String::from("export default {};\n");
+        for (index, name) in names.iter().enumerate() {
+            src.push_str(&format!("var __ignored_{index};\nexport {{ __ignored_{index} as {name} }};\n"));
+        }
+        src
+    }
 }
 
 impl Load for Loader {
     fn load(&self, f: &FileName) -> Result<ModuleData, Error> {
-        let fm = match f {
-            FileName::Real(path) => self.cm.load_file(path)?,
+        if let Some((fm, module)) = self.loaded.borrow().get(f) {
+            return Ok(ModuleData { fm: fm.clone(), module: module.clone(), helpers: Default::default() });
+        }
+
+        let _load = self.trace.span("load", f.to_string());
+        let read = self.trace.span("read", f.to_string());
+
+        // Whatever a transform command outputs is JavaScript, no matter the
+        // extension it started with.
+        let (fm, language) = match f {
+            FileName::Real(path) => {
+                let (source, language) = match &self.stdin {
+                    Some((stdin_path, source)) if stdin_path == path => (source.clone(), Language::JavaScript),
+                    _ => match self.transforms.apply(path)? {
+                        Some(source) => (source, Language::JavaScript),
+                        None => (std::fs::read_to_string(path)?, Language::of(path)),
+                    },
+                };
+                let source = pragmas::strip(&path.to_string_lossy(), &source, &self.flags)?.into_owned();
+                (self.cm.new_source_file(f.clone(), source), language)
+            },
+            FileName::Custom(name) if name == interop::HELPERS_SPECIFIER => {
+                (self.cm.new_source_file(f.clone(), interop::helpers_source(self.interop)), Language::JavaScript)
+            },
+            FileName::Custom(name) if name.starts_with(interop::MISSING_PREFIX) => {
+                (self.cm.new_source_file(f.clone(), interop::missing_source(&name[interop::MISSING_PREFIX.len()..])), Language::JavaScript)
+            },
+            FileName::Custom(name) if name.starts_with(IGNORED_PREFIX) => {
+                (self.cm.new_source_file(f.clone(), self.ignored_source(&name[IGNORED_PREFIX.len()..])), Language::JavaScript)
+            },
+            FileName::Custom(specifier) if self.virtual_modules.contains_key(specifier) => {
+                (self.cm.new_source_file(f.clone(), self.virtual_modules[specifier].clone()), Language::JavaScript)
+            },
+            FileName::Custom(url) if remote::is_remote(url) => {
+                (self.cm.new_source_file(f.clone(), self.remote.fetch(url)?), Language::JavaScript)
+            },
             _ => unreachable!(),
         };
+        drop(read);
 
-        let module = parse_file_as_module(
+        let parse = self.trace.span("parse", f.to_string());
+        let mut module = parse_file_as_module(
             &fm,
-            Syntax::Es(EsConfig {
-                ..Default::default()
-            }),
+            language.syntax(),
             EsVersion::Es2020,
             None,
             &mut vec![],
@@ -257,6 +1497,66 @@ impl Load for Loader {
             err.into_diagnostic(&handler).emit();
             panic!("failed to parse")
         });
+        drop(parse);
+
+        let _transform = self.trace.span("transform", f.to_string());
+
+        // JSX is lowered first, so the factory it calls counts as used when
+        // type stripping drops the imports nothing refers to.
+        if language.has_jsx() {
+            jsx::transform_jsx(&mut module, &self.jsx);
+        }
+
+        if language.is_typescript() {
+            let const_enums;
+            (module, const_enums) = typescript::strip_types(module)?;
+            self.const_enums.borrow_mut().extend(const_enums);
+        }
+
+        // The extension, and failing that the owning package's "type",
+        // settles what an ambiguous file is; without either it's down to
+        // what the code looks like.
+        let declared = match f {
+            FileName::Real(path) => kind::from_extension(path).or_else(|| self.package_types.declared(path)),
+            _ => None,
+        };
+        let is_commonjs = match declared {
+            Some(kind::ModuleKind::Esm) => false,
+            Some(kind::ModuleKind::CommonJs) => !interop::has_module_declarations(&module),
+            None => interop::is_commonjs(&module),
+        };
+
+        if let FileName::Real(path) = f {
+            computed::expand(&self.cm, &mut module, path, is_commonjs, self.computed_specifiers)?;
+        }
+
+        if let (Dirname::Relative, FileName::Real(path)) = (self.dirname, f) {
+            interop::inline_dirname(&mut module, path)?;
+        }
+
+        if is_commonjs {
+            module = interop::wrap_commonjs(&self.cm, module, self.require_resolve, self.no_runtime_require.as_deref(), &self.externals)?;
+        }
+
+        if self.inline_dynamic_imports {
+            module = dynamic::inline_dynamic_imports(&self.cm, module, &self.externals)?;
+        }
+
+        // An ignored module stands in for whatever it replaced, so what's
+        // imported from it isn't checked. What it exports depends on what's
+        // been loaded so far, so it isn't kept either.
+        if !matches!(f, FileName::Custom(name) if name.starts_with(IGNORED_PREFIX)) {
+            let info = analyze::module_info(&self.cm, &module);
+            if let Some(duplicate) = info.duplicate_exports.first() {
+                bail!("{duplicate}");
+            }
+            self.modules.borrow_mut().insert(f.to_string(), info);
+
+            // When two entries load a module at once, both use whichever
+            // copy was kept first.
+            let (fm, module) = self.loaded.borrow_mut().entry(f.clone()).or_insert((fm, module)).clone();
+            return Ok(ModuleData { fm, module, helpers: Default::default() });
+        }
 
         Ok(ModuleData {
             fm,
@@ -267,16 +1567,91 @@ impl Load for Loader {
 }
 
 
+/// Names the empty module an ignored specifier is replaced with.
+const IGNORED_PREFIX: &str = "please-bundle:ignored:";
+
 pub struct Resolver {
-    pub packages: HashMap<String, FileName>
+    pub packages: HashMap<String, FileName>,
+    pub import_map: Option<ImportMap>,
+    pub preserve_symlinks: bool,
+    pub allow_remote: bool,
+    pub virtual_modules: HashMap<String, String>,
+    /// Specifiers swapped for an empty module rather than resolved.
+    pub ignores: Vec<glob::Pattern>,
+    pub graph: ModuleGraph,
+    pub trace: Lrc<trace::Trace>,
 }
 
-impl Resolve for Resolver {
+/// The [Resolver] as the bundler of one entry sees it, merging identical
+/// modules with a deduper of its own.
+pub struct EntryResolver<'a> {
+    pub resolver: &'a Resolver,
+    pub deduper: dedupe::Deduper,
+}
+
+impl Resolve for EntryResolver<'_> {
     fn resolve(&self, base: &swc_common::FileName, module_specifier: &str) -> Result<swc_common::FileName, Error> {
+        // A require that might never run can be of a module that isn't
+        // there, which is left to fail once it's required.
+        if let Some(specifier) = module_specifier.strip_prefix(interop::OPTIONAL_PREFIX) {
+            return match self.resolve(base, specifier) {
+                Ok(resolved) => Ok(resolved),
+                Err(_) => Ok(FileName::Custom(format!("{}{specifier}", interop::MISSING_PREFIX))),
+            };
+        }
+
+        let _resolve = self.resolver.trace.span_of("resolve", base.to_string(), format!("{module_specifier} from {base}"));
+        let resolved = match self.resolver.resolve_specifier(base, module_specifier)? {
+            FileName::Real(path) => FileName::Real(self.deduper.representative(&path)?),
+            resolved => resolved,
+        };
+        self.resolver.graph.add_edge(base, module_specifier, &resolved);
+        Ok(resolved)
+    }
+}
+
+impl Resolver {
+    fn resolve_specifier(&self, base: &FileName, module_specifier: &str) -> Result<FileName, Error> {
+        if self.virtual_modules.contains_key(module_specifier) {
+            return Ok(FileName::Custom(module_specifier.to_string()));
+        }
+
+        if self.ignores.iter().any(|pattern| pattern.matches(module_specifier)) {
+            return Ok(FileName::Custom(format!("{IGNORED_PREFIX}{module_specifier}")));
+        }
+
+        if let (Some(import_map), FileName::Real(base_path)) = (&self.import_map, base) {
+            match import_map.resolve(base_path, module_specifier) {
+                Some(import_map::Address::Local(path)) => return Ok(FileName::Real(paths::resolve(&path, self.preserve_symlinks)?)),
+                Some(import_map::Address::External) => {
+                    bail!("'{module_specifier}' is mapped to a remote address by the import map, mark it with --external")
+                },
+                None => {},
+            }
+        }
+
         if self.packages.contains_key(module_specifier) {
             return Ok(self.packages[module_specifier].clone());
         }
 
+        if module_specifier == interop::HELPERS_SPECIFIER {
+            return Ok(FileName::Custom(module_specifier.to_string()));
+        }
+
+        let url = match base {
+            FileName::Custom(base_url) if remote::is_remote(base_url) && import_map::is_local(module_specifier) => {
+                remote::join(base_url, module_specifier)
+            },
+            _ if remote::is_remote(module_specifier) => Some(module_specifier.to_string()),
+            _ => None,
+        };
+        if let Some(url) = url {
+            if !self.allow_remote {
+                bail!("'{url}' is a remote module, pass --allow-remote to bundle it");
+            }
+            return Ok(FileName::Custom(url));
+        }
+
         if ! base.is_real() {
             return Err(anyhow!("base {base} isn't a real file, don't know what to do."));
         }
@@ -295,13 +1670,13 @@ impl Resolve for Resolver {
                 Some(path) => path,
             };
 
-            let full_path = base_dir_path.join(path).canonicalize()?;
+            let full_path = paths::resolve(&base_dir_path.join(path), self.preserve_symlinks)?;
 
-            return Ok(FileName::Real(full_path));
+            Ok(FileName::Real(full_path))
         } else {
-            return Ok(
+            Ok(
                 FileName::Real(path),
-            );
+            )
         }
     }
 }
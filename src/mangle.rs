@@ -0,0 +1,144 @@
+use rustc_hash::FxHashSet;
+use swc_atoms::JsWord;
+use swc_common::{Globals, Mark, GLOBALS};
+use swc_ecma_ast::{ClassDecl, ClassExpr, FnDecl, FnExpr, Id, Ident, Module};
+use swc_ecma_transforms_base::{
+    fixer::fixer,
+    hygiene,
+    rename::{renamer, Renamer},
+    resolver,
+};
+use swc_ecma_visit::{Visit, VisitWith, FoldWith};
+
+#[derive(Default)]
+pub struct Options {
+    /// Keep the names of functions and classes, so their `.name` is as the
+    /// source had it.
+    pub keep_names: bool,
+    /// Names that are never given to, or taken from, an identifier.
+    pub reserved: Vec<String>,
+}
+
+/// Renames the local identifiers of the bundle to the shortest names that
+/// don't clash, leaving everything else about the code as it was. Globals
+/// and exported names are left alone.
+pub fn mangle(globals: &Globals, module: Module, options: &Options) -> Module {
+    GLOBALS.set(globals, || {
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+
+        let module = module.fold_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        module
+            .fold_with(&mut renamer(
+                hygiene::Config {
+                    keep_class_names: options.keep_names,
+                    top_level_mark,
+                    ..Default::default()
+                },
+                ShortNames { options },
+            ))
+            .fold_with(&mut fixer(None))
+    })
+}
+
+struct ShortNames<'a> {
+    options: &'a Options,
+}
+
+impl Renamer for ShortNames<'_> {
+    const RESET_N: bool = false;
+    const MANGLE: bool = true;
+
+    fn preserved_ids_for_module(&mut self, module: &Module) -> FxHashSet<Id> {
+        let mut finder = PreservedFinder { options: self.options, ids: Default::default() };
+        module.visit_with(&mut finder);
+        finder.ids
+    }
+
+    fn new_name_for(&self, _: &Id, n: &mut usize) -> JsWord {
+        loop {
+            let name = short_name(*n);
+            *n += 1;
+
+            if !is_reserved_word(&name) && !self.options.reserved.contains(&name) {
+                return name.into();
+            }
+        }
+    }
+}
+
+struct PreservedFinder<'a> {
+    options: &'a Options,
+    ids: FxHashSet<Id>,
+}
+
+impl PreservedFinder<'_> {
+    fn preserve_name(&mut self, ident: &Ident) {
+        if self.options.keep_names {
+            self.ids.insert(ident.to_id());
+        }
+    }
+}
+
+impl Visit for PreservedFinder<'_> {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if self.options.reserved.iter().any(|reserved| *reserved == *ident.sym) {
+            self.ids.insert(ident.to_id());
+        }
+    }
+
+    fn visit_fn_decl(&mut self, decl: &FnDecl) {
+        self.preserve_name(&decl.ident);
+        decl.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, expr: &FnExpr) {
+        if let Some(ident) = &expr.ident {
+            self.preserve_name(ident);
+        }
+        expr.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, decl: &ClassDecl) {
+        self.preserve_name(&decl.ident);
+        decl.visit_children_with(self);
+    }
+
+    fn visit_class_expr(&mut self, expr: &ClassExpr) {
+        if let Some(ident) = &expr.ident {
+            self.preserve_name(ident);
+        }
+        expr.visit_children_with(self);
+    }
+}
+
+const FIRST_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_$";
+const REST_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_$0123456789";
+
+/// The `n`th identifier in order of length: `a`, `b`, ... `$`, `aa`, `ba`...
+fn short_name(mut n: usize) -> String {
+    let mut name = String::new();
+    name.push(FIRST_CHARS[n % FIRST_CHARS.len()] as char);
+    n /= FIRST_CHARS.len();
+
+    while n > 0 {
+        n -= 1;
+        name.push(REST_CHARS[n % REST_CHARS.len()] as char);
+        n /= REST_CHARS.len();
+    }
+
+    name
+}
+
+fn is_reserved_word(name: &str) -> bool {
+    matches!(
+        name,
+        "do" | "if" | "in" | "for" | "let" | "new" | "try" | "var" | "case" | "else" | "enum" | "eval" | "null"
+            | "this" | "true" | "void" | "with" | "await" | "break" | "catch" | "class" | "const" | "false"
+            | "super" | "throw" | "while" | "yield" | "delete" | "export" | "import" | "public" | "return"
+            | "static" | "switch" | "typeof" | "default" | "extends" | "finally" | "package" | "private"
+            | "continue" | "debugger" | "function" | "arguments" | "interface" | "protected" | "implements"
+            | "instanceof" | "undefined" | "NaN" | "Infinity"
+    )
+}
@@ -0,0 +1,311 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Error, bail};
+
+use sha2::{Digest, Sha256};
+
+use swc_common::{
+    source_map::{LineCol, SourceMapGenConfig},
+    sync::Lrc,
+    BytePos, FileName, SourceFile, SourceMap,
+};
+
+use crate::format::Format;
+use crate::paths;
+
+/// Where an entry is written under the outdir, from a template such as
+/// `[dir]/[name]-[hash]`:
+///
+/// - `[name]` is the entry's logical name,
+/// - `[dir]` is the directory of the entry's source relative to `outbase`,
+/// - `[hash]` is a hash of the emitted code.
+///
+/// `.js` is appended to the result.
+pub fn entry_path(template: &str, name: &str, source: Option<&Path>, outbase: &Path, code: &str) -> PathBuf {
+    let dir = source
+        .and_then(|source| source.parent())
+        .and_then(|dir| dir.strip_prefix(outbase).ok())
+        .map(paths::to_slash)
+        .unwrap_or_default();
+
+    let path = template
+        .replace("[name]", name)
+        .replace("[dir]", &dir)
+        .replace("[hash]", &content_hash(code));
+
+    let mut path: PathBuf = path.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+    path.as_mut_os_string().push(".js");
+    path
+}
+
+/// Where the minified copy of the entry written to `path` goes: `app.js`
+/// becomes `app.min.js`.
+pub fn minified_path(path: &Path) -> PathBuf {
+    path.with_extension("min.js")
+}
+
+/// The 1-based line on which `new` first differs from `old`.
+pub fn first_different_line(old: &str, new: &str) -> usize {
+    let mut old_lines = old.lines();
+    let mut new_lines = new.lines();
+    let mut line = 1;
+
+    while let (Some(old_line), Some(new_line)) = (old_lines.next(), new_lines.next()) {
+        if old_line != new_line {
+            break;
+        }
+        line += 1;
+    }
+
+    line
+}
+
+/// `<script>` tags loading each written file, relative to the outdir: ES
+/// modules for browsers that support them and, with `nomodule`, the
+/// System.register or AMD builds for those that don't. The page is expected
+/// to load SystemJS or an AMD loader itself for the latter. CommonJS builds
+/// can't be loaded by a page, so get no tag.
+pub fn script_tags(scripts: &[(Format, String)]) -> String {
+    scripts.iter()
+        .filter_map(|(format, src)| match format {
+            Format::Esm => Some(format!("<script type=\"module\" src=\"./{src}\"></script>\n")),
+            Format::System => Some(format!("<script nomodule>System.import(\"./{src}\");</script>\n")),
+            Format::Amd => Some(format!("<script nomodule>require([\"./{src}\"]);</script>\n")),
+            Format::Cjs => None,
+        })
+        .collect()
+}
+
+/// The deepest directory containing every one of `sources`.
+pub fn common_dir<'a>(sources: impl IntoIterator<Item = &'a Path>) -> PathBuf {
+    let mut common: Option<Vec<Component>> = None;
+
+    for dir in sources.into_iter().filter_map(|source| source.parent()) {
+        let components: Vec<Component> = dir.components().collect();
+        common = Some(match common {
+            None => components,
+            Some(common) => common.into_iter()
+                .zip(components)
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+
+    common.unwrap_or_default().into_iter().collect()
+}
+
+/// Every file under `dir`, relative to it, in a stable order.
+pub fn public_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    if !dir.is_dir() {
+        bail!("{} isn't a directory", dir.display());
+    }
+
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(next) = dirs.pop() {
+        for entry in std::fs::read_dir(&next)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if let Ok(file) = path.strip_prefix(dir) {
+                files.push(file.to_path_buf());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+pub fn content_hash(code: &str) -> String {
+    let digest = Sha256::digest(code.as_bytes());
+    digest.iter().take(4).map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Where a build writes its files and stdout. With `--audit-determinism`
+/// it's all held in memory, in the order it was written, so that two builds
+/// can be compared before either is let out.
+#[derive(Default)]
+pub struct Sink {
+    held: Option<RefCell<Held>>,
+}
+
+/// What was written, by the file it was written to or `None` for stdout.
+type Held = Vec<(Option<PathBuf>, Vec<u8>)>;
+
+impl Sink {
+    pub fn holding() -> Sink {
+        Sink { held: Some(Default::default()) }
+    }
+
+    pub fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        match &self.held {
+            Some(held) => held.borrow_mut().push((Some(path.to_path_buf()), contents.to_vec())),
+            None => std::fs::write(path, contents)?,
+        }
+        Ok(())
+    }
+
+    /// Prints `contents` on a line of its own to stdout.
+    pub fn print(&self, contents: &str) {
+        match &self.held {
+            Some(held) => held.borrow_mut().push((None, format!("{contents}\n").into_bytes())),
+            None => println!("{contents}"),
+        }
+    }
+
+    /// Writes out everything that's being held.
+    pub fn release(self) -> io::Result<()> {
+        for (path, contents) in self.held.map(RefCell::into_inner).unwrap_or_default() {
+            match path {
+                Some(path) => std::fs::write(path, contents)?,
+                None => io::stdout().write_all(&contents)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The first way what `other` holds differs from what this holds.
+    pub fn first_divergence(&self, other: &Sink) -> Option<String> {
+        let (ours, theirs) = match (&self.held, &other.held) {
+            (Some(ours), Some(theirs)) => (ours.borrow(), theirs.borrow()),
+            _ => return None,
+        };
+
+        let name = |path: &Option<PathBuf>| path.as_ref().map_or(String::from("<stdout>"), |path| path.display().to_string());
+        let mut ours = ours.iter();
+        let mut theirs = theirs.iter();
+        loop {
+            match (ours.next(), theirs.next()) {
+                (None, None) => return None,
+                (Some((path, _)), None) => return Some(format!("{} is only written by the first build", name(path))),
+                (None, Some((path, _))) => return Some(format!("{} is only written by the second build", name(path))),
+                (Some((path, _)), Some((other_path, _))) if path != other_path => {
+                    return Some(format!("the first build writes {} where the second writes {}", name(path), name(other_path)));
+                },
+                (Some((path, contents)), Some((_, other_contents))) if contents != other_contents => {
+                    let line = first_different_line(&String::from_utf8_lossy(contents), &String::from_utf8_lossy(other_contents));
+                    return Some(format!("{} differs from line {line}", name(path)));
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+/// Controls how files are named in `sources` of emitted source maps.
+pub struct SourceMapConfig {
+    /// When set, real files are listed relative to this directory rather
+    /// than by whatever (usually absolute) path they were loaded from.
+    pub relative_to: Option<PathBuf>,
+}
+
+impl SourceMapGenConfig for SourceMapConfig {
+    fn file_name_to_source(&self, f: &FileName) -> String {
+        match (f, &self.relative_to) {
+            (FileName::Real(path), Some(base)) => {
+                let path = match paths::current_dir() {
+                    Ok(cwd) if path.is_relative() => cwd.join(path),
+                    _ => path.clone(),
+                };
+                paths::to_slash(&relative_path(&path, base))
+            },
+            (FileName::Real(path), None) => paths::to_slash(path),
+            _ => f.to_string(),
+        }
+    }
+}
+
+/// `path` relative to `base`, both of which are expected to be absolute.
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    std::iter::repeat_n(Component::ParentDir, base.len() - common)
+        .chain(path[common..].iter().cloned())
+        .collect()
+}
+
+/// Problems with `mappings` that would stop a source map being built from
+/// them at all: generated positions mapped to bytes no loaded file holds.
+pub fn mapping_problems(cm: &SourceMap, mappings: &[(BytePos, LineCol)]) -> Vec<String> {
+    let files = cm.files();
+
+    mappings.iter()
+        .filter(|(pos, lc)| !pos.is_reserved_for_comments() && *pos != BytePos(u32::MAX) && !(pos.is_dummy() && lc.line == 0 && lc.col == 0))
+        .filter(|(pos, _)| !files.iter().any(|file| file.start_pos <= *pos && *pos < file.end_pos))
+        .map(|(pos, lc)| format!("generated {}:{} maps to byte {} outside every source", lc.line + 1, lc.col, pos.0))
+        .collect()
+}
+
+/// Problems with `map`, which was emitted for `code` and serialised as
+/// `written`: mappings pointing outside their source or the code, names
+/// that don't resolve and positions that don't survive a round trip
+/// through the serialised map.
+pub fn source_map_problems(cm: &SourceMap, config: &SourceMapConfig, code: &str, map: &sourcemap::SourceMap, written: &[u8]) -> Vec<String> {
+    let parsed = match sourcemap::SourceMap::from_slice(written) {
+        Ok(parsed) => parsed,
+        Err(err) => return vec![format!("doesn't parse back in: {err}")],
+    };
+
+    let sources: HashMap<String, Lrc<SourceFile>> = cm.files().iter()
+        .map(|file| (config.file_name_to_source(&file.name), file.clone()))
+        .collect();
+    let generated: Vec<&str> = code.lines().collect();
+
+    let mut problems = vec![];
+
+    for token in parsed.tokens() {
+        let at = format!("generated {}:{}", token.get_dst_line() + 1, token.get_dst_col());
+
+        match generated.get(token.get_dst_line() as usize) {
+            Some(line) if token.get_dst_col() as usize <= line.encode_utf16().count() => {},
+            _ => problems.push(format!("{at} is past the end of the code")),
+        }
+
+        match token.get_source().map(|source| (source, sources.get(source))) {
+            None => problems.push(format!("{at} maps to no source")),
+            Some((source, None)) => problems.push(format!("{at} maps to {source}, which isn't a source that was bundled")),
+            Some((source, Some(file))) => match file.get_line(token.get_src_line() as usize) {
+                Some(line) if token.get_src_col() as usize <= line.encode_utf16().count() => {},
+                _ => problems.push(format!("{at} maps to {source}:{}:{}, past the end of the source", token.get_src_line() + 1, token.get_src_col())),
+            },
+        }
+
+        if token.has_name() && token.get_name().is_none() {
+            problems.push(format!("{at} has a name that isn't in the map's names"));
+        }
+    }
+
+    if parsed.get_token_count() != map.get_token_count() {
+        problems.push(format!("has {} mappings once written, {} before", parsed.get_token_count(), map.get_token_count()));
+    }
+
+    // A sample of generated positions are looked up in the written map, and
+    // should come back to where the map was built to send them. Positions
+    // mapped more than once can come back to any of them, so are left out.
+    let step = (map.get_token_count() as usize / 1000).max(1);
+    for (index, token) in map.tokens().enumerate().step_by(step) {
+        let next = map.get_token(index as u32 + 1).map(|next| (next.get_dst_line(), next.get_dst_col()));
+        if next == Some((token.get_dst_line(), token.get_dst_col())) {
+            continue;
+        }
+
+        let found = parsed.lookup_token(token.get_dst_line(), token.get_dst_col())
+            .map(|found| (found.get_dst_line(), found.get_dst_col(), found.get_source(), found.get_src_line(), found.get_src_col()));
+        let expected = (token.get_dst_line(), token.get_dst_col(), token.get_source(), token.get_src_line(), token.get_src_col());
+
+        if found != Some(expected) {
+            problems.push(format!("generated {}:{} looks up to somewhere other than where it was mapped", token.get_dst_line() + 1, token.get_dst_col()));
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,132 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Canonicalizes `path` into the form used for every module key, see
+/// [normalize].
+pub fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    Ok(normalize(&path.canonicalize()?))
+}
+
+/// Makes `path` absolute and resolves `.` and `..` lexically, leaving any
+/// symlinks along the way in place, like Node's `--preserve-symlinks`.
+pub fn absolute(path: &Path) -> io::Result<PathBuf> {
+    let path = if path.is_relative() {
+        current_dir()?.join(path)
+    } else {
+        path.to_path_buf()
+    };
+
+    let mut absolute = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                absolute.pop();
+            },
+            component => absolute.push(component),
+        }
+    }
+
+    if !absolute.exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} doesn't exist", absolute.display())));
+    }
+
+    Ok(normalize(&absolute))
+}
+
+/// [canonicalize], or [absolute] when symlinks are to be preserved.
+pub fn resolve(path: &Path, preserve_symlinks: bool) -> io::Result<PathBuf> {
+    if preserve_symlinks {
+        absolute(path)
+    } else {
+        canonicalize(path)
+    }
+}
+
+pub fn current_dir() -> io::Result<PathBuf> {
+    Ok(normalize(&std::env::current_dir()?))
+}
+
+/// On Windows `canonicalize` hands back verbatim `\\?\C:\...` and
+/// `\\?\UNC\server\share\...` paths, and drive letters come back in whatever
+/// case they were typed. Strip the verbatim prefix and upper case the drive
+/// so the same file always gets the same key. Elsewhere this is a no-op.
+pub fn normalize(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(normalize_windows(&path.to_string_lossy()))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+fn normalize_windows(path: &str) -> String {
+    let path = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{rest}")
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        rest.to_string()
+    } else {
+        path.to_string()
+    };
+
+    match path.as_bytes() {
+        [drive, b':', ..] if drive.is_ascii_lowercase() => {
+            let mut path = path;
+            path[..1].make_ascii_uppercase();
+            path
+        },
+        _ => path,
+    }
+}
+
+/// `path` with `/` separators, as used in source maps and specifiers.
+pub fn to_slash(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Writes a cache file so that whoever reads it, in this process or another
+/// build running alongside, sees all of it or nothing: it's written next to
+/// `path` and then renamed into place.
+pub fn write_cache_file(path: &Path, contents: &str) -> io::Result<()> {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(format!(".{}-{}.partial", std::process::id(), WRITES.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&partial, contents)?;
+    std::fs::rename(&partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{normalize_windows, to_slash};
+
+    #[test]
+    fn upper_cases_drive_letters() {
+        assert_eq!(normalize_windows(r"c:\src\main.js"), r"C:\src\main.js");
+        assert_eq!(normalize_windows(r"D:\src\main.js"), r"D:\src\main.js");
+    }
+
+    #[test]
+    fn strips_verbatim_prefixes() {
+        assert_eq!(normalize_windows(r"\\?\c:\src\main.js"), r"C:\src\main.js");
+        assert_eq!(normalize_windows(r"\\?\UNC\server\share\main.js"), r"\\server\share\main.js");
+    }
+
+    #[test]
+    fn leaves_other_paths_alone() {
+        assert_eq!(normalize_windows(r"\\server\share\main.js"), r"\\server\share\main.js");
+        assert_eq!(normalize_windows(r"src\main.js"), r"src\main.js");
+        assert_eq!(normalize_windows("/src/main.js"), "/src/main.js");
+    }
+
+    #[test]
+    fn slashes_backslash_separators() {
+        assert_eq!(to_slash(Path::new(&normalize_windows(r"\\?\c:\src\lib\index.js"))), "C:/src/lib/index.js");
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Error, anyhow};
+
+use serde::Deserialize;
+
+use crate::versions::{Range, Version};
+
+#[derive(Deserialize)]
+struct PackageFields {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default, rename = "peerDependencies")]
+    peer_dependencies: BTreeMap<String, String>,
+}
+
+/// Peer dependencies of the packages in `package_dirs` that the version of
+/// the peer bundled alongside them doesn't satisfy, as messages naming
+/// both packages. A peer that isn't bundled is left to whoever provides it
+/// at runtime, and a package bundled more than once is checked at each of
+/// its versions, which is how two copies of one package end up in a bundle
+/// in the first place. A range or version that can't be compared is
+/// reported rather than failing the build.
+pub fn mismatches(package_dirs: &[PathBuf]) -> Result<Vec<String>, Error> {
+    let mut packages = vec![];
+    for package_dir in package_dirs {
+        let package_path = package_dir.join("package.json");
+        let package: PackageFields = serde_json::from_str(&std::fs::read_to_string(&package_path)?)
+            .map_err(|why| anyhow!("failed to parse {package_path:?}: {why}"))?;
+        packages.push(package);
+    }
+
+    let mut mismatches = vec![];
+    for package in &packages {
+        let id = match (&package.name, &package.version) {
+            (Some(name), Some(version)) => format!("{name}@{version}"),
+            (Some(name), None) => name.clone(),
+            (None, _) => continue,
+        };
+
+        for (peer, range) in &package.peer_dependencies {
+            let parsed: Range = match range.parse() {
+                Ok(parsed) => parsed,
+                Err(why) => {
+                    mismatches.push(format!("{id} has a peer dependency on {peer} that can't be checked: {why}"));
+                    continue;
+                },
+            };
+
+            let bundled = packages.iter()
+                .filter(|other| other.name.as_ref() == Some(peer))
+                .filter_map(|other| other.version.as_deref());
+            for version in bundled {
+                match version.parse::<Version>() {
+                    Ok(parsed_version) if parsed.matches(&parsed_version) => {},
+                    Ok(_) => mismatches.push(format!("{id} wants {peer}@{range} but {peer}@{version} is bundled")),
+                    Err(why) => mismatches.push(format!("{id} wants {peer}@{range} but {peer}@{version} is bundled, which can't be checked: {why}")),
+                }
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
@@ -0,0 +1,117 @@
+use crate::consts::{Const, Value};
+use crate::format::Format;
+
+/// The runtime a build is for, settling the externals, export conditions,
+/// main fields and, unless they're given, the format and constants that
+/// suit it.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    /// Node, with its builtin modules left to it.
+    Node,
+    /// Electron's main process, which has Node's builtins and `electron`.
+    ElectronMain,
+    /// A preload script, which runs sandboxed with `electron` and the few
+    /// builtins the sandbox allows.
+    ElectronPreload,
+    /// A renderer process, which is a web page.
+    ElectronRenderer,
+    /// Cloudflare's Workers runtime, with its `cloudflare:` modules and,
+    /// under `nodejs_compat`, Node's builtins as `node:`.
+    Workerd,
+    /// Deno, which has Node's builtins as `node:`.
+    Deno,
+    /// Bun, with Node's builtins and its own `bun:` modules.
+    Bun,
+}
+
+/// Node's builtin modules, all of which can also be imported as `node:`.
+const NODE_BUILTINS: &[&str] = &[
+    "assert", "assert/strict", "async_hooks", "buffer", "child_process", "cluster", "console", "constants",
+    "crypto", "dgram", "diagnostics_channel", "dns", "dns/promises", "domain", "events", "fs", "fs/promises",
+    "http", "http2", "https", "inspector", "module", "net", "os", "path", "path/posix", "path/win32",
+    "perf_hooks", "process", "punycode", "querystring", "readline", "readline/promises", "repl", "stream",
+    "stream/consumers", "stream/promises", "stream/web", "string_decoder", "sys", "timers", "timers/promises",
+    "tls", "trace_events", "tty", "url", "util", "util/types", "v8", "vm", "wasi", "worker_threads", "zlib",
+];
+
+/// The builtins a sandboxed preload script can `require`.
+const PRELOAD_BUILTINS: &[&str] = &["events", "timers", "url"];
+
+const WORKERD_MODULES: &[&str] = &["cloudflare:email", "cloudflare:sockets", "cloudflare:workers", "cloudflare:workflows"];
+
+const BUN_MODULES: &[&str] = &["bun", "bun:ffi", "bun:jsc", "bun:sqlite", "bun:test"];
+
+impl Platform {
+    /// Specifiers the runtime provides, which are left external.
+    pub fn externals(&self) -> Vec<String> {
+        // Runtimes that only have Node's builtins as `node:` don't get
+        // the bare names, so they're bundled as packages like anything else.
+        let (builtins, bare, own): (&[&str], bool, &[&str]) = match self {
+            Platform::Node => (NODE_BUILTINS, true, &[]),
+            Platform::ElectronMain => (NODE_BUILTINS, true, &["electron"]),
+            Platform::ElectronPreload => (PRELOAD_BUILTINS, true, &["electron"]),
+            Platform::ElectronRenderer => (&[], false, &[]),
+            Platform::Workerd => (NODE_BUILTINS, false, WORKERD_MODULES),
+            Platform::Deno => (NODE_BUILTINS, false, &[]),
+            Platform::Bun => (NODE_BUILTINS, true, BUN_MODULES),
+        };
+
+        builtins.iter()
+            .flat_map(|builtin| bare.then(|| builtin.to_string()).into_iter().chain([format!("node:{builtin}")]))
+            .chain(own.iter().map(|module| module.to_string()))
+            .collect()
+    }
+
+    /// Export conditions to resolve packages with, on top of the mode's.
+    pub fn conditions(&self) -> &'static [&'static str] {
+        match self {
+            Platform::Node | Platform::ElectronMain | Platform::ElectronPreload => &["node"],
+            Platform::ElectronRenderer => &["browser"],
+            Platform::Workerd => &["workerd", "worker", "browser"],
+            Platform::Deno => &["deno", "node"],
+            Platform::Bun => &["bun", "node"],
+        }
+    }
+
+    /// Whether ES modules can make a `require` with `createRequire` from
+    /// `node:module`.
+    pub fn has_create_require(&self) -> bool {
+        matches!(self, Platform::Node | Platform::ElectronMain | Platform::Deno | Platform::Bun)
+    }
+
+    /// The format to emit when none is asked for. Electron's main process
+    /// and preload scripts load their entry point with `require`.
+    pub fn format(&self) -> Option<Format> {
+        match self {
+            Platform::ElectronMain | Platform::ElectronPreload => Some(Format::Cjs),
+            _ => None,
+        }
+    }
+
+    /// Constants the runtime is known to have, which `--const` can override.
+    /// Electron sets `process.type` to the kind of process code runs in.
+    pub fn consts(&self) -> Vec<Const> {
+        let process_type = match self {
+            Platform::ElectronMain => "browser",
+            Platform::ElectronPreload | Platform::ElectronRenderer => "renderer",
+            _ => return vec![],
+        };
+
+        vec![Const {
+            path: vec![String::from("process"), String::from("type")],
+            value: Value::String(process_type.to_string()),
+        }]
+    }
+
+    /// The `package.json` fields naming a package's entry point, in the
+    /// order they're tried.
+    pub fn main_fields(&self) -> &'static [&'static str] {
+        match self {
+            Platform::Node | Platform::ElectronMain | Platform::ElectronPreload | Platform::Deno | Platform::Bun => &["module", "main"],
+            Platform::ElectronRenderer | Platform::Workerd => &DEFAULT_MAIN_FIELDS,
+        }
+    }
+}
+
+/// The main fields used without a platform, as for the browser.
+pub const DEFAULT_MAIN_FIELDS: [&str; 3] = ["browser", "module", "main"];
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Error, anyhow};
+
+use serde::Deserialize;
+
+use crate::versions::{Range, Version};
+
+/// Which packages a build may bundle, read from a JSON file such as
+///
+/// ```json
+/// { "allow": { "lib": "^1.0.0", "vendor_a": "*" }, "deny": { "left-pad": "*" } }
+/// ```
+///
+/// giving a version range for each package named. A package a range in
+/// `deny` matches is never allowed and, when there's an `allow`, a package
+/// has to be in it at a version its range matches.
+pub struct Policy {
+    allow: Option<BTreeMap<String, Range>>,
+    deny: BTreeMap<String, Range>,
+}
+
+#[derive(Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    allow: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    deny: BTreeMap<String, String>,
+}
+
+impl Policy {
+    pub fn load(path: &Path) -> Result<Policy, Error> {
+        let file: PolicyFile = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|why| anyhow!("failed to parse package policy {path:?}: {why}"))?;
+
+        let ranges = |packages: BTreeMap<String, String>| packages.into_iter()
+            .map(|(name, range)| Ok((name, range.parse()?)))
+            .collect::<Result<BTreeMap<String, Range>, Error>>()
+            .map_err(|why| anyhow!("in package policy {path:?}: {why}"));
+
+        Ok(Policy {
+            allow: file.allow.map(ranges).transpose()?,
+            deny: ranges(file.deny)?,
+        })
+    }
+
+    /// Why the package can't be bundled, if it can't. A package without a
+    /// version only matches ranges that match every version.
+    pub fn violation(&self, name: &str, version: Option<&str>) -> Result<Option<String>, Error> {
+        let version = version.map(str::parse::<Version>).transpose()?;
+        let matches = |range: &Range| match &version {
+            Some(version) => range.matches(version),
+            None => range.is_any(),
+        };
+
+        if let Some(range) = self.deny.get(name).filter(|range| matches(range)) {
+            return Ok(Some(format!("is denied by the policy ({range})")));
+        }
+
+        Ok(match self.allow.as_ref().map(|allow| allow.get(name)) {
+            Some(None) => Some(String::from("isn't allowed by the policy")),
+            Some(Some(range)) if !matches(range) => Some(format!("isn't allowed by the policy ({range})")),
+            _ => None,
+        })
+    }
+}
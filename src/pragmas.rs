@@ -0,0 +1,98 @@
+use std::borrow::Cow;
+
+use anyhow::{Error, bail};
+
+/// Strips the regions of `source` guarded by comment pragmas whose
+/// condition doesn't hold for `flags`:
+///
+/// ```js
+/// // #if NAME
+/// // #elif !OTHER
+/// // #else
+/// // #endif
+/// ```
+///
+/// Pragmas nest. Stripped lines, along with the pragmas themselves, are
+/// left empty rather than removed so positions in the rest of the file,
+/// and so its source map, stay where they were.
+pub fn strip<'a>(name: &str, source: &'a str, flags: &[String]) -> Result<Cow<'a, str>, Error> {
+    if !source.lines().any(|line| pragma(line).is_some()) {
+        return Ok(Cow::Borrowed(source));
+    }
+
+    struct Region {
+        line: usize,
+        /// Whether the region's surroundings are kept at all.
+        outer: bool,
+        /// Whether a branch of the region has been kept yet.
+        taken: bool,
+        seen_else: bool,
+    }
+
+    let holds = |condition: &str| match condition.strip_prefix('!') {
+        Some(flag) => !flags.iter().any(|set| set == flag.trim()),
+        None => flags.iter().any(|set| set == condition),
+    };
+
+    let mut regions: Vec<Region> = vec![];
+    let mut keeping = true;
+    let mut stripped = String::with_capacity(source.len());
+
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        let number = index + 1;
+
+        match pragma(line) {
+            Some(("if", condition)) => {
+                let taken = keeping && holds(condition);
+                regions.push(Region { line: number, outer: keeping, taken, seen_else: false });
+                keeping = taken;
+            },
+            Some(("elif", condition)) => match regions.last_mut() {
+                Some(region) if !region.seen_else => {
+                    keeping = region.outer && !region.taken && holds(condition);
+                    region.taken |= keeping;
+                },
+                _ => bail!("{name}:{number}: #elif without an #if to go with it"),
+            },
+            Some(("else", _)) => match regions.last_mut() {
+                Some(region) if !region.seen_else => {
+                    keeping = region.outer && !region.taken;
+                    region.taken = true;
+                    region.seen_else = true;
+                },
+                _ => bail!("{name}:{number}: #else without an #if to go with it"),
+            },
+            Some(("endif", _)) => match regions.pop() {
+                Some(region) => keeping = region.outer,
+                None => bail!("{name}:{number}: #endif without an #if to go with it"),
+            },
+            _ if keeping => {
+                stripped.push_str(line);
+                continue;
+            },
+            _ => {},
+        }
+
+        if line.ends_with('\n') {
+            stripped.push('\n');
+        }
+    }
+
+    if let Some(region) = regions.last() {
+        bail!("{name}:{}: #if is never closed with an #endif", region.line);
+    }
+
+    Ok(Cow::Owned(stripped))
+}
+
+/// The directive of a pragma line and what follows it, e.g. `("if", "NAME")`.
+fn pragma(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim().strip_prefix("//")?.trim_start().strip_prefix('#')?;
+    let (directive, condition) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+    match directive {
+        "if" | "elif" if !condition.trim().is_empty() => Some((directive, condition.trim())),
+        "else" | "endif" => Some((directive, "")),
+        _ => None,
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+
+use serde::Serialize;
+
+use swc_common::{source_map::LineCol, BytePos, FileName, SourceMap};
+
+use crate::licenses::PackageLicense;
+use crate::{output, paths};
+
+/// A stretch of an emitted bundle that came from one file.
+#[derive(Serialize)]
+pub struct Region {
+    /// Byte offsets into the bundle, `end` being exclusive.
+    pub start: usize,
+    pub end: usize,
+    /// Relative to the current directory.
+    pub file: String,
+    /// `name@version` of the package the file belongs to, when it's in one.
+    pub package: Option<String>,
+}
+
+/// Which file and package each stretch of `code` came from, going by the
+/// mappings it was emitted with, so that a crash in production can be put
+/// down to a dependency without having its source maps to hand. Code the
+/// bundler generated belongs to no file and is left out.
+pub fn regions(cm: &SourceMap, code: &str, mappings: &[(BytePos, LineCol)]) -> Result<Vec<Region>, Error> {
+    let current_dir = paths::current_dir()?;
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+
+    let files = cm.files();
+    let mut starts: Vec<(usize, Option<FileName>)> = mappings.iter()
+        .filter_map(|(pos, lc)| {
+            let offset = offset(code, &line_starts, lc)?;
+            let file = files.iter()
+                .filter(|_| !pos.is_dummy() && !pos.is_reserved_for_comments())
+                .find(|file| file.start_pos <= *pos && *pos < file.end_pos)
+                .map(|file| file.name.clone());
+            Some((offset, file))
+        })
+        .collect();
+    starts.sort_by_key(|(offset, _)| *offset);
+    starts.dedup_by(|next, previous| next.1 == previous.1);
+
+    let mut packages = HashMap::new();
+    let mut regions = vec![];
+    for (i, (start, file)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(code.len(), |(end, _)| *end);
+        let (file, package) = match file {
+            Some(FileName::Real(path)) => (
+                paths::to_slash(&output::relative_path(path, &current_dir)),
+                path.parent().and_then(|dir| package_of(dir, &mut packages)),
+            ),
+            Some(name) => (name.to_string(), None),
+            None => continue,
+        };
+
+        if start < &end {
+            regions.push(Region { start: *start, end, file, package });
+        }
+    }
+
+    Ok(regions)
+}
+
+/// The byte offset of `lc`, whose column counts UTF-16 code units.
+fn offset(code: &str, line_starts: &[usize], lc: &LineCol) -> Option<usize> {
+    let line_start = *line_starts.get(lc.line as usize)?;
+    let line = code[line_start..].split('\n').next()?;
+
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= lc.col as usize {
+            return Some(line_start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(line_start + line.len())
+}
+
+/// The package owning `dir`, found through the nearest `package.json`.
+fn package_of(dir: &Path, packages: &mut HashMap<PathBuf, Option<String>>) -> Option<String> {
+    if let Some(package) = packages.get(dir) {
+        return package.clone();
+    }
+
+    let package = if dir.join("package.json").exists() {
+        PackageLicense::read(dir).ok().map(|package| package.id())
+    } else {
+        dir.parent().and_then(|parent| package_of(parent, packages))
+    };
+
+    packages.insert(dir.to_path_buf(), package.clone());
+    package
+}
@@ -0,0 +1,130 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Error, anyhow, bail};
+
+use sha2::{Digest, Sha256};
+
+use swc_common::sync::Lock;
+
+use crate::paths;
+
+pub fn is_remote(specifier: &str) -> bool {
+    specifier.starts_with("https://")
+}
+
+/// `specifier` resolved against the URL of the module importing it, for
+/// the relative and absolute paths a remote module imports its siblings by.
+pub fn join(base: &str, specifier: &str) -> Option<String> {
+    let rest = base.strip_prefix("https://")?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+
+    let mut segments: Vec<&str> = match specifier.strip_prefix('/') {
+        Some(_) => vec![],
+        None => {
+            let path = path.split(['?', '#']).next().unwrap_or_default();
+            path.split('/').filter(|segment| !segment.is_empty()).collect()
+        },
+    };
+    if !specifier.starts_with('/') && !path.ends_with('/') {
+        segments.pop();
+    }
+
+    for segment in specifier.split('/') {
+        match segment {
+            "" | "." => {},
+            ".." => { segments.pop(); },
+            segment => segments.push(segment),
+        }
+    }
+
+    Some(format!("https://{host}/{}", segments.join("/")))
+}
+
+/// Fetches `https://` modules with curl, keeping a copy of each under the
+/// cache directory and pinning its hash in a lock file when given one.
+///
+/// A module whose hash doesn't match the lock file fails the build; modules
+/// the lock file doesn't know about are added to it.
+pub struct Remote {
+    cache_dir: Option<PathBuf>,
+    lock_path: Option<PathBuf>,
+    lock: Lock<BTreeMap<String, String>>,
+    fetched: Lock<HashMap<String, String>>,
+}
+
+impl Remote {
+    pub fn new(cache_dir: Option<PathBuf>, lock_path: Option<PathBuf>) -> Result<Remote, Error> {
+        let lock = match &lock_path {
+            Some(path) if path.exists() => serde_json::from_str(&std::fs::read_to_string(path)?)
+                .map_err(|why| anyhow!("failed to parse lock file {path:?}: {why}"))?,
+            _ => BTreeMap::new(),
+        };
+
+        Ok(Remote {
+            cache_dir,
+            lock_path,
+            lock: Lock::new(lock),
+            fetched: Default::default(),
+        })
+    }
+
+    pub fn fetch(&self, url: &str) -> Result<String, Error> {
+        if let Some(source) = self.fetched.borrow().get(url) {
+            return Ok(source.clone());
+        }
+
+        let cached_path = self.cache_dir.as_ref().map(|dir| dir.join("remote").join(format!("{}.js", hash(url.as_bytes()))));
+        let source = match cached_path.as_ref().filter(|cached_path| cached_path.exists()) {
+            Some(cached_path) => std::fs::read_to_string(cached_path)?,
+            None => download(url)?,
+        };
+
+        let integrity = hash(source.as_bytes());
+        let pinned = self.lock.borrow().get(url).cloned();
+        match pinned {
+            Some(pinned) if pinned != integrity => {
+                bail!("{url} has hash {integrity} but the lock file pins {pinned}")
+            },
+            Some(_) => {},
+            None => { self.lock.borrow_mut().insert(url.to_string(), integrity); },
+        }
+
+        if let Some(cached_path) = cached_path.filter(|cached_path| !cached_path.exists()) {
+            paths::write_cache_file(&cached_path, &source)?;
+        }
+
+        self.fetched.borrow_mut().insert(url.to_string(), source.clone());
+        Ok(source)
+    }
+
+    /// The lock file, with every module fetched so far pinned, and where it
+    /// goes.
+    pub fn lock_file(&self) -> Result<Option<(&Path, Vec<u8>)>, Error> {
+        match &self.lock_path {
+            Some(path) => Ok(Some((path, serde_json::to_vec_pretty(&*self.lock.borrow())?))),
+            None => Ok(None),
+        }
+    }
+}
+
+fn download(url: &str) -> Result<String, Error> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", url])
+        .output()
+        .map_err(|why| anyhow!("failed to run curl for {url}: {why}"))?;
+
+    if !output.status.success() {
+        bail!("failed to fetch {url}: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn hash(contents: &[u8]) -> String {
+    Sha256::digest(contents).iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+
+use serde_json::{Value, json};
+
+use sha2::{Digest, Sha256};
+
+use crate::analyze::ModuleInfo;
+use crate::licenses::PackageLicense;
+use crate::{output, paths};
+
+/// A CycloneDX bill of materials listing the packages in `package_dirs`,
+/// each with a hash of the files of it that were bundled, so the hash only
+/// changes when what ships does.
+pub fn cyclonedx(package_dirs: &[PathBuf], modules: &BTreeMap<String, ModuleInfo>) -> Result<Value, Error> {
+    let current_dir = paths::current_dir()?;
+    let mut components = vec![];
+
+    for package_dir in package_dirs {
+        let package = PackageLicense::read(package_dir)?;
+        let purl = match &package.version {
+            Some(version) => format!("pkg:npm/{}@{version}", package.name.replace('@', "%40")),
+            None => format!("pkg:npm/{}", package.name.replace('@', "%40")),
+        };
+
+        let mut hasher = Sha256::new();
+        for file in modules.keys().map(Path::new).filter(|file| file.starts_with(package_dir)) {
+            hasher.update(paths::to_slash(file.strip_prefix(package_dir)?).as_bytes());
+            hasher.update([0]);
+            hasher.update(std::fs::read(file)?);
+        }
+        let hash: String = hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect();
+
+        let mut component = json!({
+            "type": "library",
+            "bom-ref": purl,
+            "name": package.name,
+            "purl": purl,
+            "hashes": [{ "alg": "SHA-256", "content": hash }],
+            "properties": [{
+                "name": "please-bundle:path",
+                "value": paths::to_slash(&output::relative_path(package_dir, &current_dir)),
+            }],
+        });
+        if let Some(version) = &package.version {
+            component["version"] = json!(version);
+        }
+        if let Some(license) = &package.license {
+            component["licenses"] = json!([{ "expression": license }]);
+        }
+
+        components.push(component);
+    }
+
+    Ok(json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "tools": [{ "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") }],
+        },
+        "components": components,
+    }))
+}
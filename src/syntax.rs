@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use swc_ecma_parser::{EsConfig, Syntax, TsConfig};
+
+/// What a file is written in, going by its extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    JavaScript,
+    Jsx,
+    TypeScript,
+    Tsx,
+}
+
+impl Language {
+    pub fn of(path: &Path) -> Language {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsx") => Language::Jsx,
+            Some("ts" | "mts" | "cts") => Language::TypeScript,
+            Some("tsx") => Language::Tsx,
+            _ => Language::JavaScript,
+        }
+    }
+
+    pub fn syntax(&self) -> Syntax {
+        match self {
+            Language::JavaScript => Syntax::Es(EsConfig::default()),
+            Language::Jsx => Syntax::Es(EsConfig { jsx: true, ..Default::default() }),
+            Language::TypeScript => Syntax::Typescript(TsConfig::default()),
+            Language::Tsx => Syntax::Typescript(TsConfig { tsx: true, ..Default::default() }),
+        }
+    }
+
+    pub fn is_typescript(&self) -> bool {
+        matches!(self, Language::TypeScript | Language::Tsx)
+    }
+
+    pub fn has_jsx(&self) -> bool {
+        matches!(self, Language::Jsx | Language::Tsx)
+    }
+}
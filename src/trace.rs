@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Error;
+
+use swc_common::sync::Lock;
+
+/// Timings of the phases of a build, for `--trace` and `--profile-modules`.
+/// Nothing is recorded unless it's enabled.
+pub struct Trace {
+    enabled: bool,
+    start: Instant,
+    events: Lock<Vec<Event>>,
+}
+
+struct Event {
+    phase: &'static str,
+    /// The module the time is put down to.
+    module: String,
+    name: String,
+    start: Duration,
+    duration: Duration,
+}
+
+/// A phase being timed, recorded when it's dropped.
+pub struct Span<'a> {
+    trace: &'a Trace,
+    phase: &'static str,
+    module: String,
+    name: String,
+    start: Instant,
+}
+
+impl Trace {
+    pub fn new(enabled: bool) -> Self {
+        Trace { enabled, start: Instant::now(), events: Default::default() }
+    }
+
+    /// Starts timing `phase` of `name`, such as the `parse` of a module.
+    pub fn span(&self, phase: &'static str, name: impl Into<String>) -> Option<Span<'_>> {
+        let name = name.into();
+        self.span_of(phase, name.clone(), name)
+    }
+
+    /// Starts timing `phase` of `name`, putting the time down to `module`.
+    pub fn span_of(&self, phase: &'static str, module: impl Into<String>, name: impl Into<String>) -> Option<Span<'_>> {
+        self.enabled.then(|| Span { trace: self, phase, module: module.into(), name: name.into(), start: Instant::now() })
+    }
+
+    /// The `top` modules that took longest to read, parse, transform and
+    /// resolve the imports of, slowest first, as lines breaking the time
+    /// down by phase.
+    pub fn module_report(&self, top: usize) -> Vec<String> {
+        let mut by_module: BTreeMap<&str, BTreeMap<&str, Duration>> = BTreeMap::new();
+        let events = self.events.borrow();
+        for event in events.iter().filter(|event| MODULE_PHASES.contains(&event.phase)) {
+            *by_module.entry(&event.module).or_default().entry(event.phase).or_default() += event.duration;
+        }
+
+        let mut totals: Vec<(Duration, &str, BTreeMap<&str, Duration>)> = by_module.into_iter()
+            .map(|(module, phases)| (phases.values().sum(), module, phases))
+            .collect();
+        totals.sort_by(|(a, a_module, _), (b, b_module, _)| b.cmp(a).then(a_module.cmp(b_module)));
+
+        totals.into_iter()
+            .take(top)
+            .map(|(total, module, phases)| {
+                let phases: Vec<String> = MODULE_PHASES.iter()
+                    .filter_map(|phase| phases.get(phase).map(|duration| format!("{phase} {}", millis(*duration))))
+                    .collect();
+                format!("{} {module} ({})", millis(total), phases.join(", "))
+            })
+            .collect()
+    }
+
+    /// Writes the recorded phases as a Chrome trace, which Perfetto and
+    /// `chrome://tracing` both open.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let events: Vec<serde_json::Value> = self.events.borrow().iter()
+            .map(|event| serde_json::json!({
+                "name": format!("{} {}", event.phase, event.name),
+                "cat": event.phase,
+                "ph": "X",
+                "ts": event.start.as_micros() as u64,
+                "dur": event.duration.as_micros() as u64,
+                "pid": 1,
+                "tid": 1,
+                "args": { "module": event.name },
+            }))
+            .collect();
+
+        std::fs::write(path, serde_json::to_vec(&serde_json::json!({ "traceEvents": events }))?)?;
+        Ok(())
+    }
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        self.trace.events.borrow_mut().push(Event {
+            phase: self.phase,
+            module: std::mem::take(&mut self.module),
+            name: std::mem::take(&mut self.name),
+            start: self.start - self.trace.start,
+            duration: self.start.elapsed(),
+        });
+    }
+}
+
+/// The phases time is put down to a module for, which don't overlap.
+const MODULE_PHASES: [&str; 4] = ["read", "parse", "transform", "resolve"];
+
+fn millis(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
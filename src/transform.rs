@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{Error, anyhow, bail};
+
+use sha2::{Digest, Sha256};
+
+use swc_common::sync::Lock;
+
+use crate::paths;
+
+/// External commands that turn files with a given extension into
+/// JavaScript: the file is piped to the command's stdin and its stdout
+/// becomes the module source. Commands are split on whitespace rather than
+/// run through a shell.
+///
+/// Output is cached by a hash of the command and the input, in memory and,
+/// when a cache directory is given, on disk across runs. When a cache URL is
+/// given too, output is shared through it as `<url>/<hash>.js`, fetched with
+/// a GET before running the command and stored with a PUT after.
+pub struct Transforms {
+    commands: HashMap<String, String>,
+    cache_dir: Option<PathBuf>,
+    cache_url: Option<String>,
+    cache: Lock<HashMap<String, String>>,
+}
+
+impl Transforms {
+    pub fn new(commands: impl IntoIterator<Item = (String, String)>, cache_dir: Option<PathBuf>, cache_url: Option<String>) -> Transforms {
+        Transforms {
+            commands: commands.into_iter()
+                .map(|(extension, command)| (extension.trim_start_matches('.').to_string(), command))
+                .collect(),
+            cache_dir,
+            cache_url: cache_url.map(|url| url.trim_end_matches('/').to_string()),
+            cache: Default::default(),
+        }
+    }
+
+    /// The transformed source of `path`, or `None` when no command handles
+    /// its extension.
+    pub fn apply(&self, path: &Path) -> Result<Option<String>, Error> {
+        let command = match path.extension().and_then(|extension| extension.to_str()).and_then(|extension| self.commands.get(extension)) {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+
+        let input = std::fs::read(path)?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(command.as_bytes());
+        hasher.update([0]);
+        hasher.update(&input);
+        let key: String = hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect();
+
+        if let Some(output) = self.cache.borrow().get(&key) {
+            return Ok(Some(output.clone()));
+        }
+
+        let cached_path = self.cache_dir.as_ref().map(|dir| dir.join(format!("{key}.js")));
+        let output = match cached_path.as_ref().filter(|cached_path| cached_path.exists()) {
+            Some(cached_path) => std::fs::read_to_string(cached_path)?,
+            None => {
+                let shared_url = self.cache_url.as_ref().map(|url| format!("{url}/{key}.js"));
+                let output = match shared_url.as_deref().and_then(get) {
+                    Some(output) => output,
+                    None => {
+                        let output = run(command, path, &input)?;
+                        // The shared cache only saves time, so a build doesn't
+                        // fail for want of it.
+                        if let Some(Err(why)) = shared_url.map(|url| put(&url, &output)) {
+                            eprintln!("warning: {why}");
+                        }
+                        output
+                    },
+                };
+                if let Some(cached_path) = &cached_path {
+                    paths::write_cache_file(cached_path, &output)?;
+                }
+                output
+            },
+        };
+
+        self.cache.borrow_mut().insert(key, output.clone());
+        Ok(Some(output))
+    }
+}
+
+fn run(command: &str, path: &Path, input: &[u8]) -> Result<String, Error> {
+    let mut words = command.split_whitespace();
+    let program = match words.next() {
+        Some(program) => program,
+        None => bail!("empty transform command for {path:?}"),
+    };
+
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    // The input is written from another thread so a command streaming its
+    // output can't fill the pipe while we're still writing. One that exits
+    // without reading all its input is reported by its exit status below,
+    // not as a broken pipe here.
+    let mut stdin = child.stdin.take();
+    let output = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || match stdin.as_mut().map(|stdin| stdin.write_all(input)) {
+            Some(Err(why)) if why.kind() != std::io::ErrorKind::BrokenPipe => Err(why),
+            _ => Ok(()),
+        });
+        let output = child.wait_with_output();
+        writer.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+        output
+    })?;
+    if !output.status.success() {
+        bail!("'{command}' failed on {path:?} with {}", output.status);
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// What the shared cache holds at `url`, or `None` when it holds nothing or
+/// can't be reached.
+fn get(url: &str) -> Option<String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--fail", "--location", url])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout).ok()
+}
+
+fn put(url: &str, contents: &str) -> Result<(), Error> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--upload-file", "-", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|why| anyhow!("failed to run curl for {url}: {why}"))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(contents.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("failed to store {url} in the shared cache: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(())
+}
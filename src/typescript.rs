@@ -0,0 +1,328 @@
+use std::collections::HashSet;
+
+use anyhow::{Error, bail};
+
+use swc_common::DUMMY_SP;
+use swc_ecma_ast::{
+    AssignExpr, AssignOp, Class, ClassMember, ClassMethod, ClassProp, Constructor, Decl,
+    DefaultDecl, ExportSpecifier, Expr, ExprStmt, Ident, ImportDecl, ImportSpecifier, MemberExpr, MemberProp, Module,
+    ModuleDecl, ModuleExportName, ModuleItem, Param, ParamOrTsParamProp, Pat, PatOrExpr, PrivateMethod, Stmt, ThisExpr,
+    TsParamPropParam, TsTypeAnn, TsTypeParamDecl, TsTypeParamInstantiation, VarDeclarator,
+};
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+use crate::enums;
+
+/// Strips TypeScript down to the JavaScript it describes: annotations,
+/// interfaces, type aliases, `declare`d code, overloads and type-only
+/// imports and exports go, parameter properties become assignments in the
+/// constructor, and imports only ever used as types are dropped.
+///
+/// Enums are compiled to objects, see [enums::lower_enums], and the const
+/// ones handed back with the module. Namespaces have runtime behaviour of
+/// their own and are rejected, as are `import x = require()` and
+/// `export =`.
+pub fn strip_types(mut module: Module) -> Result<(Module, enums::ConstEnums), Error> {
+    let const_enums = enums::lower_enums(&mut module)?;
+
+    let mut stripper = TypeStripper { type_names: HashSet::new(), unsupported: None };
+    module.visit_mut_with(&mut stripper);
+
+    if let Some(unsupported) = stripper.unsupported {
+        bail!("{unsupported} isn't supported in TypeScript files");
+    }
+
+    elide_unused_imports(&mut module);
+    elide_type_exports(&mut module, &stripper.type_names);
+
+    Ok((module, const_enums))
+}
+
+struct TypeStripper {
+    type_names: HashSet<String>,
+    unsupported: Option<&'static str>,
+}
+
+impl TypeStripper {
+    /// Whether `decl` only exists at the type level, noting the names of
+    /// interfaces and type aliases for export elision.
+    fn is_type_only(&mut self, decl: &Decl) -> bool {
+        match decl {
+            Decl::TsInterface(interface) => {
+                self.type_names.insert(interface.id.sym.to_string());
+                true
+            },
+            Decl::TsTypeAlias(alias) => {
+                self.type_names.insert(alias.id.sym.to_string());
+                true
+            },
+            Decl::Class(class) => class.declare,
+            Decl::Fn(function) => function.declare || function.function.body.is_none(),
+            Decl::Var(var) => var.declare,
+            Decl::TsEnum(enum_decl) => enum_decl.declare,
+            Decl::TsModule(module) if module.declare => true,
+            Decl::TsModule(_) => {
+                self.unsupported.get_or_insert("namespace");
+                false
+            },
+        }
+    }
+
+    fn keep_module_item(&mut self, item: &mut ModuleItem) -> bool {
+        match item {
+            ModuleItem::Stmt(Stmt::Decl(decl)) => !self.is_type_only(decl),
+            ModuleItem::ModuleDecl(decl) => match decl {
+                ModuleDecl::Import(import) if import.type_only => false,
+                ModuleDecl::Import(import) => {
+                    import.specifiers.retain(|specifier| {
+                        !matches!(specifier, ImportSpecifier::Named(named) if named.is_type_only)
+                    });
+                    true
+                },
+                ModuleDecl::ExportNamed(export) if export.type_only => false,
+                ModuleDecl::ExportNamed(export) => {
+                    let had_specifiers = !export.specifiers.is_empty();
+                    export.specifiers.retain(|specifier| {
+                        !matches!(specifier, ExportSpecifier::Named(named) if named.is_type_only)
+                    });
+                    !had_specifiers || !export.specifiers.is_empty()
+                },
+                ModuleDecl::ExportDecl(export) => !self.is_type_only(&export.decl),
+                ModuleDecl::ExportDefaultDecl(export) => !matches!(export.decl, DefaultDecl::TsInterfaceDecl(_)),
+                ModuleDecl::TsNamespaceExport(_) => false,
+                ModuleDecl::TsImportEquals(_) => {
+                    self.unsupported.get_or_insert("import = require()");
+                    true
+                },
+                ModuleDecl::TsExportAssignment(_) => {
+                    self.unsupported.get_or_insert("export =");
+                    true
+                },
+                _ => true,
+            },
+            ModuleItem::Stmt(_) => true,
+        }
+    }
+}
+
+impl VisitMut for TypeStripper {
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.retain_mut(|item| self.keep_module_item(item));
+        items.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.retain(|stmt| !matches!(stmt, Stmt::Decl(decl) if self.is_type_only(decl)));
+        stmts.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_opt_ts_type_ann(&mut self, type_ann: &mut Option<Box<TsTypeAnn>>) {
+        *type_ann = None;
+    }
+
+    fn visit_mut_opt_ts_type_param_decl(&mut self, type_params: &mut Option<Box<TsTypeParamDecl>>) {
+        *type_params = None;
+    }
+
+    fn visit_mut_opt_ts_type_param_instantiation(&mut self, type_args: &mut Option<Box<TsTypeParamInstantiation>>) {
+        *type_args = None;
+    }
+
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        loop {
+            let inner = match expr {
+                Expr::TsAs(as_expr) => &mut as_expr.expr,
+                Expr::TsTypeAssertion(assertion) => &mut assertion.expr,
+                Expr::TsConstAssertion(assertion) => &mut assertion.expr,
+                Expr::TsNonNull(non_null) => &mut non_null.expr,
+                Expr::TsSatisfies(satisfies) => &mut satisfies.expr,
+                Expr::TsInstantiation(instantiation) => &mut instantiation.expr,
+                _ => break,
+            };
+            *expr = *std::mem::replace(inner, Box::new(Expr::Invalid(swc_ecma_ast::Invalid { span: DUMMY_SP })));
+        }
+
+        expr.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_ident(&mut self, ident: &mut Ident) {
+        ident.optional = false;
+    }
+
+    fn visit_mut_var_declarator(&mut self, declarator: &mut VarDeclarator) {
+        declarator.definite = false;
+        declarator.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_params(&mut self, params: &mut Vec<Param>) {
+        params.retain(|param| !matches!(&param.pat, Pat::Ident(ident) if &*ident.id.sym == "this"));
+        params.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_class(&mut self, class: &mut Class) {
+        class.implements.clear();
+        class.is_abstract = false;
+
+        class.body.retain(|member| match member {
+            ClassMember::TsIndexSignature(_) => false,
+            ClassMember::ClassProp(prop) => !prop.declare && !prop.is_abstract,
+            ClassMember::Method(method) => method.function.body.is_some(),
+            ClassMember::PrivateMethod(method) => method.function.body.is_some(),
+            ClassMember::Constructor(constructor) => constructor.body.is_some(),
+            _ => true,
+        });
+
+        class.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_class_prop(&mut self, prop: &mut ClassProp) {
+        prop.accessibility = None;
+        prop.is_optional = false;
+        prop.is_override = false;
+        prop.readonly = false;
+        prop.definite = false;
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_class_method(&mut self, method: &mut ClassMethod) {
+        method.accessibility = None;
+        method.is_optional = false;
+        method.is_override = false;
+        method.is_abstract = false;
+        method.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_private_method(&mut self, method: &mut PrivateMethod) {
+        method.accessibility = None;
+        method.is_optional = false;
+        method.is_override = false;
+        method.is_abstract = false;
+        method.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_constructor(&mut self, constructor: &mut Constructor) {
+        constructor.accessibility = None;
+
+        let mut assigned = vec![];
+        for param in &mut constructor.params {
+            if let ParamOrTsParamProp::TsParamProp(prop) = param {
+                let pat = match &prop.param {
+                    TsParamPropParam::Ident(ident) => Pat::Ident(ident.clone()),
+                    TsParamPropParam::Assign(assign) => Pat::Assign(assign.clone()),
+                };
+                let name = match &prop.param {
+                    TsParamPropParam::Ident(ident) => ident.id.clone(),
+                    TsParamPropParam::Assign(assign) => match &*assign.left {
+                        Pat::Ident(ident) => ident.id.clone(),
+                        _ => continue,
+                    },
+                };
+
+                assigned.push(name);
+                *param = ParamOrTsParamProp::Param(Param { span: prop.span, decorators: prop.decorators.clone(), pat });
+            }
+        }
+
+        constructor.visit_mut_children_with(self);
+
+        if let (Some(body), false) = (&mut constructor.body, assigned.is_empty()) {
+            let assignments = assigned.into_iter().map(|name| this_assignment(Ident::new(name.sym.clone(), DUMMY_SP)));
+
+            // Assignments to `this` have to wait for `super()`.
+            let after_super = body.stmts.iter()
+                .position(is_super_call)
+                .map(|index| index + 1)
+                .unwrap_or(0);
+            body.stmts.splice(after_super..after_super, assignments);
+        }
+    }
+}
+
+fn is_super_call(stmt: &Stmt) -> bool {
+    matches!(
+        stmt,
+        Stmt::Expr(ExprStmt { expr, .. }) if matches!(&**expr, Expr::Call(call) if matches!(call.callee, swc_ecma_ast::Callee::Super(_)))
+    )
+}
+
+/// `this.name = name;`
+fn this_assignment(name: Ident) -> Stmt {
+    Stmt::Expr(ExprStmt {
+        span: DUMMY_SP,
+        expr: Box::new(Expr::Assign(AssignExpr {
+            span: DUMMY_SP,
+            op: AssignOp::Assign,
+            left: PatOrExpr::Expr(Box::new(Expr::Member(MemberExpr {
+                span: DUMMY_SP,
+                obj: Box::new(Expr::This(ThisExpr { span: DUMMY_SP })),
+                prop: MemberProp::Ident(name.clone()),
+            }))),
+            right: Box::new(Expr::Ident(name)),
+        })),
+    })
+}
+
+/// Drops import specifiers that nothing refers to now types are gone, and
+/// imports left without any. Imports that never had specifiers are kept for
+/// their side effects.
+fn elide_unused_imports(module: &mut Module) {
+    let mut collector = IdentCollector { names: HashSet::new() };
+    for item in &module.body {
+        if !matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))) {
+            item.visit_with(&mut collector);
+        }
+    }
+
+    module.body.retain_mut(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl { specifiers, .. })) if !specifiers.is_empty() => {
+            specifiers.retain(|specifier| {
+                let local = match specifier {
+                    ImportSpecifier::Named(named) => &named.local,
+                    ImportSpecifier::Default(default) => &default.local,
+                    ImportSpecifier::Namespace(namespace) => &namespace.local,
+                };
+                collector.names.contains(&*local.sym)
+            });
+            !specifiers.is_empty()
+        },
+        _ => true,
+    });
+}
+
+/// Drops local exports of names that were only ever types.
+fn elide_type_exports(module: &mut Module, type_names: &HashSet<String>) {
+    let mut declared = IdentCollector { names: HashSet::new() };
+    for item in &module.body {
+        match item {
+            ModuleItem::Stmt(Stmt::Decl(decl)) | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(swc_ecma_ast::ExportDecl { decl, .. })) => {
+                decl.visit_with(&mut declared);
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => import.visit_with(&mut declared),
+            _ => {},
+        }
+    }
+
+    module.body.retain_mut(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) if export.src.is_none() && !export.specifiers.is_empty() => {
+            export.specifiers.retain(|specifier| match specifier {
+                ExportSpecifier::Named(named) => match &named.orig {
+                    ModuleExportName::Ident(orig) => !type_names.contains(&*orig.sym) || declared.names.contains(&*orig.sym),
+                    ModuleExportName::Str(_) => true,
+                },
+                _ => true,
+            });
+            !export.specifiers.is_empty()
+        },
+        _ => true,
+    });
+}
+
+struct IdentCollector {
+    names: HashSet<String>,
+}
+
+impl Visit for IdentCollector {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.names.insert(ident.sym.to_string());
+    }
+}
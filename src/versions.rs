@@ -0,0 +1,286 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Error, anyhow, bail};
+
+/// A package version, `major.minor.patch` with an optional prerelease.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    prerelease: Vec<String>,
+}
+
+impl Version {
+    fn new(major: u64, minor: u64, patch: u64) -> Version {
+        Version { major, minor, patch, prerelease: vec![] }
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(version: &str) -> Result<Version, Error> {
+        match Partial::parse(version)? {
+            Partial { major: Some(major), minor: Some(minor), patch: Some(patch), prerelease } => {
+                Ok(Version { major, minor, patch, prerelease })
+            },
+            _ => bail!("'{version}' isn't a version"),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.prerelease.is_empty() {
+            write!(f, "-{}", self.prerelease.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Version) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.prerelease.is_empty(), other.prerelease.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => {
+                    for (ours, theirs) in self.prerelease.iter().zip(&other.prerelease) {
+                        let order = match (ours.parse::<u64>(), theirs.parse::<u64>()) {
+                            (Ok(ours), Ok(theirs)) => ours.cmp(&theirs),
+                            (Ok(_), Err(_)) => Ordering::Less,
+                            (Err(_), Ok(_)) => Ordering::Greater,
+                            (Err(_), Err(_)) => ours.cmp(theirs),
+                        };
+                        if order != Ordering::Equal {
+                            return order;
+                        }
+                    }
+                    self.prerelease.len().cmp(&other.prerelease.len())
+                },
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A version that may leave parts out or give them as `x` or `*`, as
+/// ranges do.
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    prerelease: Vec<String>,
+}
+
+impl Partial {
+    fn parse(version: &str) -> Result<Partial, Error> {
+        let version = version.trim().trim_start_matches(['v', '=']);
+        // Build metadata doesn't take part in comparisons.
+        let version = version.split('+').next().unwrap_or_default();
+        let (numbers, prerelease) = match version.split_once('-') {
+            Some((numbers, prerelease)) => (numbers, prerelease.split('.').map(String::from).collect()),
+            None => (version, vec![]),
+        };
+
+        let mut parts = numbers.split('.').map(|part| match part {
+            "x" | "X" | "*" | "" => Ok(None),
+            part => part.parse::<u64>().map(Some).map_err(|_| anyhow!("'{version}' isn't a version")),
+        });
+        let major = parts.next().transpose()?.flatten();
+        let minor = parts.next().transpose()?.flatten().filter(|_| major.is_some());
+        let patch = parts.next().transpose()?.flatten().filter(|_| minor.is_some());
+        if parts.next().is_some() {
+            bail!("'{version}' isn't a version");
+        }
+
+        Ok(Partial { major, minor, patch, prerelease })
+    }
+
+    /// The lowest version this covers.
+    fn floor(&self) -> Version {
+        Version {
+            major: self.major.unwrap_or(0),
+            minor: self.minor.unwrap_or(0),
+            patch: self.patch.unwrap_or(0),
+            prerelease: self.prerelease.clone(),
+        }
+    }
+
+    /// The lowest version above everything this covers, `None` when it
+    /// covers everything.
+    fn ceiling(&self) -> Option<Version> {
+        match (self.major, self.minor, self.patch) {
+            (None, _, _) => None,
+            (Some(major), None, _) => Some(Version::new(major + 1, 0, 0)),
+            (Some(major), Some(minor), None) => Some(Version::new(major, minor + 1, 0)),
+            (Some(major), Some(minor), Some(patch)) => Some(Version::new(major, minor, patch + 1)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+}
+
+#[derive(Debug)]
+struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Less => version < &self.version,
+            Op::LessEqual => version <= &self.version,
+            Op::Greater => version > &self.version,
+            Op::GreaterEqual => version >= &self.version,
+            Op::Equal => version == &self.version,
+        }
+    }
+}
+
+/// An npm version range such as `^1.2.0`, `>=1 <3` or `1.x || 2.x`.
+#[derive(Debug)]
+pub struct Range {
+    source: String,
+    /// Any of these sets, each matching when all its comparators do.
+    sets: Vec<Vec<Comparator>>,
+}
+
+impl Range {
+    pub fn matches(&self, version: &Version) -> bool {
+        self.sets.iter().any(|set| {
+            // Like npm, a prerelease only matches a range that mentions a
+            // prerelease of the same version.
+            let prerelease_allowed = version.prerelease.is_empty() || set.iter().any(|comparator| {
+                !comparator.version.prerelease.is_empty()
+                    && (comparator.version.major, comparator.version.minor, comparator.version.patch)
+                        == (version.major, version.minor, version.patch)
+            });
+            prerelease_allowed && set.iter().all(|comparator| comparator.matches(version))
+        })
+    }
+
+    /// Whether every version matches, as with `*`.
+    pub fn is_any(&self) -> bool {
+        self.sets.iter().any(|set| set.is_empty())
+    }
+}
+
+impl FromStr for Range {
+    type Err = Error;
+
+    fn from_str(range: &str) -> Result<Range, Error> {
+        let sets = range.split("||")
+            .map(|set| parse_set(set).map_err(|why| anyhow!("'{range}' isn't a version range: {why}")))
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Range { source: range.trim().to_string(), sets })
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn parse_set(set: &str) -> Result<Vec<Comparator>, Error> {
+    if let Some((low, high)) = set.split_once(" - ") {
+        let (low, high) = (Partial::parse(low)?, Partial::parse(high)?);
+        let mut comparators = vec![Comparator { op: Op::GreaterEqual, version: low.floor() }];
+        match (high.patch, high.ceiling()) {
+            (Some(_), _) => comparators.push(Comparator { op: Op::LessEqual, version: high.floor() }),
+            (None, Some(ceiling)) => comparators.push(Comparator { op: Op::Less, version: ceiling }),
+            (None, None) => {},
+        }
+        return Ok(comparators);
+    }
+
+    // Operators can be written apart from their versions, `>= 1.2`.
+    let mut tokens: Vec<String> = vec![];
+    for token in set.split_whitespace() {
+        match tokens.last_mut() {
+            Some(last) if matches!(last.as_str(), "<" | "<=" | ">" | ">=" | "=" | "^" | "~") => last.push_str(token),
+            _ => tokens.push(token.to_string()),
+        }
+    }
+
+    let mut comparators = vec![];
+    for token in &tokens {
+        let (op, version) = match token.find(|c: char| !matches!(c, '<' | '>' | '=' | '^' | '~')) {
+            Some(i) => token.split_at(i),
+            None => (token.as_str(), ""),
+        };
+        let partial = Partial::parse(version)?;
+        let floor = partial.floor();
+        let ceiling = partial.ceiling();
+
+        let mut push = |op, version| comparators.push(Comparator { op, version });
+        match op {
+            "" | "=" => match (partial.patch, ceiling) {
+                (Some(_), _) => push(Op::Equal, floor),
+                (None, Some(ceiling)) => {
+                    push(Op::GreaterEqual, floor);
+                    push(Op::Less, ceiling);
+                },
+                (None, None) => {},
+            },
+            "^" => {
+                let ceiling = match (partial.major, partial.minor, partial.patch) {
+                    (None, _, _) => None,
+                    (Some(0), Some(0), Some(patch)) => Some(Version::new(0, 0, patch + 1)),
+                    (Some(0), Some(minor), _) => Some(Version::new(0, minor + 1, 0)),
+                    (Some(major), _, _) => Some(Version::new(major + 1, 0, 0)),
+                };
+                push(Op::GreaterEqual, floor);
+                if let Some(ceiling) = ceiling {
+                    push(Op::Less, ceiling);
+                }
+            },
+            "~" | "~>" => {
+                let ceiling = match (partial.major, partial.minor) {
+                    (None, _) => None,
+                    (Some(major), None) => Some(Version::new(major + 1, 0, 0)),
+                    (Some(major), Some(minor)) => Some(Version::new(major, minor + 1, 0)),
+                };
+                push(Op::GreaterEqual, floor);
+                if let Some(ceiling) = ceiling {
+                    push(Op::Less, ceiling);
+                }
+            },
+            ">" => match (partial.patch, ceiling) {
+                (Some(_), _) => push(Op::Greater, floor),
+                (None, Some(ceiling)) => push(Op::GreaterEqual, ceiling),
+                // Nothing is above every version.
+                (None, None) => push(Op::Less, Version::new(0, 0, 0)),
+            },
+            ">=" => push(Op::GreaterEqual, floor),
+            "<" => push(Op::Less, floor),
+            "<=" => match (partial.patch, ceiling) {
+                (Some(_), _) => push(Op::LessEqual, floor),
+                (None, Some(ceiling)) => push(Op::Less, ceiling),
+                (None, None) => {},
+            },
+            op => bail!("unknown operator '{op}'"),
+        }
+    }
+
+    Ok(comparators)
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use anyhow::{Error, anyhow, bail};
+
+use crate::interop;
+
+/// Sources of modules that only exist in memory, by specifier: either given
+/// as JavaScript or as JSON, which is exported as the default along with a
+/// named export for each top level key that's a valid identifier.
+pub fn sources(modules: &[(String, String)], json_modules: &[(String, String)]) -> Result<HashMap<String, String>, Error> {
+    let mut sources = HashMap::new();
+
+    for (specifier, source) in modules {
+        insert(&mut sources, specifier, source.clone())?;
+    }
+    for (specifier, json) in json_modules {
+        insert(&mut sources, specifier, json_source(specifier, json)?)?;
+    }
+
+    Ok(sources)
+}
+
+fn insert(sources: &mut HashMap<String, String>, specifier: &str, source: String) -> Result<(), Error> {
+    if sources.insert(specifier.to_string(), source).is_some() {
+        bail!("virtual module {specifier:?} is defined more than once");
+    }
+    Ok(())
+}
+
+fn json_source(specifier: &str, json: &str) -> Result<String, Error> {
+    let value: serde_json::Value = serde_json::from_str(json)
+        .map_err(|why| anyhow!("virtual module {specifier:?} isn't valid JSON: {why}"))?;
+
+    let mut src = format!("const __virtual_json = {value};\nexport default __virtual_json;\n");
+    if let serde_json::Value::Object(object) = &value {
+        for key in object.keys().filter(|key| interop::is_identifier_name(key) && *key != "default") {
+            src.push_str(&format!("export const {key} = __virtual_json.{key};\n"));
+        }
+    }
+
+    Ok(src)
+}
@@ -2,11 +2,404 @@
 
 set -euo pipefail
 
-if [ "$(./target/debug/please-bundle | node)" = "bibble wibble" ]
+BUNDLE=./target/debug/please-bundle
+PACKAGES=(-p example/third_party/js/lib -p example/third_party/js/cjs_lib -p example/third_party/js/vendor_a -p example/third_party/js/vendor_b)
+FAILED=0
+OUT="$(mktemp -d)"
+trap 'rm -rf "$OUT"' EXIT
+
+# Remote modules are served from a pre-filled cache, keyed by the hash of
+# their URL, so the tests never touch the network.
+mkdir -p "$OUT/cache/remote"
+for file in index.js name.js
+do
+	url_hash="$(printf '%s' "https://esm.example.com/greet/$file" | sha256sum | cut -d' ' -f1)"
+	cp "example/remote/greet/$file" "$OUT/cache/remote/$url_hash.js"
+done
+
+expect() {
+	expect_with "node --input-type=module" "$@"
+}
+
+expect_with() {
+	local runner="$1"
+	local expected="$2"
+	shift 2
+
+	local actual
+	actual="$("$BUNDLE" "${PACKAGES[@]}" "$@" 2>/dev/null | $runner)"
+
+	if [ "$actual" = "$expected" ]
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* gave '$actual', expected '$expected'"
+		FAILED=1
+	fi
+}
+
+expect_error() {
+	local expected="$1"
+	shift
+
+	local stderr
+	if stderr="$("$BUNDLE" "${PACKAGES[@]}" "$@" 2>&1 >/dev/null)"
+	then
+		echo "boo: $* succeeded, expected it to fail with '$expected'"
+		FAILED=1
+	elif grep -qF -- "$expected" <<< "$stderr"
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* didn't fail with '$expected'"
+		FAILED=1
+	fi
+}
+
+expect_stderr() {
+	local expected="$1"
+	shift
+
+	local stderr
+	if ! stderr="$("$BUNDLE" "${PACKAGES[@]}" "$@" 2>&1 >/dev/null)"
+	then
+		echo "boo: $* failed, expected it to succeed with '$expected'"
+		FAILED=1
+	elif grep -qF -- "$expected" <<< "$stderr"
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* didn't print '$expected'"
+		FAILED=1
+	fi
+}
+
+expect_file() {
+	local file="$1"
+	local expected="$2"
+	shift 2
+
+	"$BUNDLE" "${PACKAGES[@]}" "$@" >/dev/null 2>&1 || true
+
+	if grep -qF -- "$expected" "$file"
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* didn't write '$expected' to $file"
+		FAILED=1
+	fi
+}
+
+expect_file_without() {
+	local file="$1"
+	local unexpected="$2"
+	shift 2
+
+	"$BUNDLE" "${PACKAGES[@]}" "$@" >/dev/null 2>&1 || true
+
+	if [ -f "$file" ] && ! grep -qF -- "$unexpected" "$file"
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* wrote '$unexpected' to $file"
+		FAILED=1
+	fi
+}
+
+expect_no_file() {
+	local file="$1"
+	shift
+
+	"$BUNDLE" "${PACKAGES[@]}" "$@" >/dev/null 2>&1 || true
+
+	if [ ! -e "$file" ]
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* wrote $file"
+		FAILED=1
+	fi
+}
+
+expect_outdir() {
+	local output="$1"
+	local expected="$2"
+	shift 2
+
+	"$BUNDLE" "${PACKAGES[@]}" --outdir "$OUT" "$@" >/dev/null 2>&1 || true
+
+	local actual
+	actual="$(node "$OUT/$output" 2>/dev/null || true)"
+
+	if [ "$actual" = "$expected" ]
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* wrote $output giving '$actual', expected '$expected'"
+		FAILED=1
+	fi
+}
+
+expect_round_trip() {
+	local output="$1"
+	local compressed="$2"
+	local decompress="$3"
+	shift 3
+
+	"$BUNDLE" "${PACKAGES[@]}" "$@" >/dev/null 2>&1 || true
+
+	if [ -e "$output" ] && $decompress < "$compressed" 2>/dev/null | cmp -s - "$output"
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* wrote $compressed that '$decompress' doesn't turn back into $output"
+		FAILED=1
+	fi
+}
+
+expect_same_outdirs() {
+	rm -rf "$OUT/first" "$OUT/second"
+	"$BUNDLE" "${PACKAGES[@]}" --outdir "$OUT/first" "$@" >/dev/null 2>&1 || true
+	"$BUNDLE" "${PACKAGES[@]}" --outdir "$OUT/second" "$@" >/dev/null 2>&1 || true
+
+	if [ -d "$OUT/first" ] && diff -r "$OUT/first" "$OUT/second" >/dev/null
+	then
+		echo "yay: $*"
+	else
+		echo "boo: $* wrote different outdirs from one build to the next"
+		FAILED=1
+	fi
+}
+
+expect "bibble wibble" example/src/main.js
+expect "bibble wibble" example/src/main.js
+expect "bibble wibble" "example/src/m*.js"
+expect "wobble!" example/src/interop/main.js
+expect "wobble!" --interop node example/src/interop/main.js
+expect "wobble!" example/src/interop/named.js
+expect "string" example/src/interop/es_module.js
+expect "object" --interop node example/src/interop/es_module.js
+expect "hello emitter" --platform node example/src/interop/emitter.cjs
+expect "MODULE_NOT_FOUND skipped object" example/src/interop/optional.cjs
+expect_with "node --input-type=commonjs" "hello emitter" --platform node --format cjs example/src/interop/emitter.cjs
+expect "bibble wibble" --import-map example/src/import_map/importmap.json example/src/import_map/main.js
+expect "scoped" --import-map example/src/import_map/scoped.json example/src/import_map/scoped/main.js
+expect "scoped" --import-map example/src/import_map/rooted.json example/src/import_map/scoped/main.js
+expect "wibble" --stdin --stdin-resolve-dir example/src <<< 'import { bibble } from "./local.js"; console.log(bibble());'
+expect "real sibling" example/src/symlinks/app/main.js
+expect "linked sibling" --preserve-symlinks example/src/symlinks/app/main.js
+expect "ab" example/src/circular/a.js
+expect "1" example/src/dedupe/main.js
+expect "production feature" -p example/third_party/js/conditional example/src/conditions/main.js
+expect "development feature" -p example/third_party/js/conditional --mode development example/src/conditions/main.js
+expect "production feature" -p example/third_party/js/conditional --mode development --conditions production example/src/conditions/main.js
+expect "node function function" -p example/third_party/js/runtime_specific --platform node example/src/platform/main.js
+expect "browser" -p example/third_party/js/runtime_specific --platform electron-renderer example/src/platform/renderer.js
+expect "default" -p example/third_party/js/runtime_specific example/src/platform/renderer.js
+expect_with "grep -c require(\"electron\")" "1" -p example/third_party/js/runtime_specific --platform electron-main example/src/platform/electron.js
+expect_error "failed to resolve node:path" -p example/third_party/js/runtime_specific --platform electron-preload example/src/platform/main.js
+expect_with "node --input-type=commonjs" "browser function" -p example/third_party/js/runtime_specific --platform electron-main example/src/platform/process.js
+expect_with "node --input-type=commonjs" "renderer function" -p example/third_party/js/runtime_specific --platform electron-preload example/src/platform/process.js
+expect "renderer browser" -p example/third_party/js/runtime_specific --platform electron-renderer example/src/platform/process_renderer.js
+expect "utility function" -p example/third_party/js/runtime_specific --platform electron-main --format esm --const process.type:string=utility example/src/platform/process.js
+expect_with "grep -c cloudflare:sockets" "1" -p example/third_party/js/runtime_specific --platform workerd example/src/platform/worker.js
+expect "deno" -p example/third_party/js/runtime_specific --platform deno example/src/platform/renderer.js
+expect_error "failed to resolve fs" -p example/third_party/js/runtime_specific --platform deno example/src/platform/main.js
+expect "bun function function" -p example/third_party/js/runtime_specific --platform bun example/src/platform/main.js
+expect "node /" -p example/third_party/js/runtime_specific --platform node example/src/platform/require.js
+expect "dirname paths.cjs true" --platform node --dirname relative example/src/dirname/main.js
+expect_with "grep -c fileURLToPath" "3" --platform node --dirname import-meta example/src/dirname/main.js
+expect_error "dirname/resolve.cjs:3:32: require.resolve has nothing to resolve to once bundled" --platform node example/src/dirname/resolve.js
+expect_with "grep -c createRequire" "2" --platform node --require-resolve external example/src/dirname/resolve.js
+expect_with "grep -c return.require.resolve" "1" --require-resolve external --format cjs example/src/dirname/installed.cjs
+expect_error "dirname/installed.cjs:3:5: --require-resolve=external leaves require.resolve to the runtime, but ES modules only have a require" --require-resolve external example/src/dirname/installed.cjs
+expect_error "but System.register modules have no require" --require-resolve external --format system example/src/dirname/installed.cjs
+expect "paths.cjs" --platform node --dirname relative --require-resolve bundle example/src/dirname/resolve.js
+expect "./paths.cjs MODULE_NOT_FOUND" --platform node --dirname relative --require-resolve bundle example/src/dirname/installed.cjs
+expect_error "dirname/computed_resolve.cjs:3:13: require.resolve of a computed specifier can't be bundled" --require-resolve bundle example/src/dirname/computed_resolve.cjs
+expect_with "grep -c import" "2" -p example/third_party/js/runtime_specific --platform node example/src/platform/main.js
+expect "undefined esm" example/src/types/esm/main.js
+expect "object" example/src/types/cjs/main.js
+expect "object" example/src/types/untyped/main.js
+expect "cjs" example/src/kinds/main.mjs
+expect "hello typescript" example/src/typescript/main.ts
+expect "0 Green 5 named 3 square" example/src/typescript/enum.ts
+expect "read 3 2" example/src/typescript/permissions.ts
+expect_file_without "$OUT/enums/permissions.js" "Permission.All" --outdir "$OUT/enums" example/src/typescript/permissions.ts
+expect '<p class="greeting" data-kind="jsx"><b>hello jsx</b></p>' --jsx-factory h --jsx-fragment Fragment example/src/jsx/main.jsx
+expect '<b>hello tsx</b>' example/src/tsx/main.tsx
+expect '<p key="first" class="greeting"><b>hello runtime</b></p>' -p example/third_party/js/string_jsx --jsx-import-source string-jsx example/src/jsx_runtime/main.jsx
+expect "module" -p example/third_party/js/fields example/src/fields/main.js
+expect "main" -p example/third_party/js/fields --main-fields main,module example/src/fields/main.js
+expect "object undefined" --ignore "moment/locale/*" --ignore fs example/src/ignore/main.js
+expect "hi bundle 1.2.3" --virtual 'virtual:greeting=export const greeting = "hi";' --virtual-json 'virtual:build-info={"name": "bundle", "version": "1.2.3"}' example/src/virtual/main.js
+expect "bibble wibble" --inline example/src/main.js
+expect "bibble wibble" --no-dce --no-hygiene --no-fixer example/src/main.js
+expect "bibble wibble" --mangle example/src/main.js
+expect "a hello mangle" --mangle example/src/mangle/main.js
+expect "greet hello mangle" --mangle --keep-names example/src/mangle/main.js
+expect "greet hello mangle" --mangle --reserved greet example/src/mangle/main.js
+expect "a hello mangle" --config example/configs/flat.json
+expect "greet hello mangle" --config example/configs/flat.json --keep-names
+expect "" --dry-run example/src/main.js
+expect "bibble wibble" --verify-output example/src/main.js
+expect "" --write=false example/src/main.js
+expect "bonjour hallo" --inline-dynamic-imports --computed-specifiers glob example/src/computed/main.js
+expect_error "computed/main.js:4:25: import() of a computed specifier can't be followed" --inline-dynamic-imports --computed-specifiers error example/src/computed/main.js
+expect_error "computed/greet.cjs:2:12: require() of a computed specifier can't be followed" --inline-dynamic-imports --computed-specifiers error example/src/computed/greet.cjs
+expect "lazy" --inline-dynamic-imports example/src/dynamic/main.js
+expect "manifest" --packages-manifest example/src/manifest/packages.json example/src/manifest/main.js
+expect "manifest" --packages-manifest example/src/manifest/packages.txt example/src/manifest/main.js
+expect "manifest" @example/src/manifest/args.txt
+expect "bibble wibble" --lockfile example/lockfiles/package-lock.json example/src/main.js
+expect "bibble wibble" --lockfile example/lockfiles/pnpm-lock.yaml example/src/main.js
+expect "QUIET" --transform upper="sed s/quiet/QUIET/" example/src/transform/main.js
+expect "QUIET" --transform upper="sed s/quiet/QUIET/" --cache-dir "$OUT/cache" example/src/transform/main.js
+expect "QUIET" --transform upper="sed s/quiet/QUIET/" --cache-dir "$OUT/cache" example/src/transform/main.js
+expect_error "'false' failed" --transform upper="false" example/src/transform/main.js
+mkdir -p "$OUT/large"
+{ for i in $(seq 20000); do echo "// padding the module out past what a pipe holds, line $i"; done; cat example/src/transform/shout.upper; } > "$OUT/large/shout.upper"
+cp example/src/transform/main.js "$OUT/large/main.js"
+expect "quiet" --transform upper="cat" "$OUT/large/main.js"
+mkdir -p "$OUT/shared"
+expect "QUIET" --transform upper="sed s/quiet/QUIET/" --cache-url "file://$OUT/shared" example/src/transform/main.js
+cp "$OUT/shared/"*.js "$OUT/shared/$({ printf 'false\0'; cat example/src/transform/shout.upper; } | sha256sum | cut -d' ' -f1).js"
+expect "QUIET" --transform upper="false" --cache-url "file://$OUT/shared" example/src/transform/main.js
+expect "hello remote" --allow-remote --cache-dir "$OUT/cache" example/src/remote/main.js
+expect_file "$OUT/remote.lock.json" '"https://esm.example.com/greet/name.js"' --allow-remote --cache-dir "$OUT/cache" --remote-lock "$OUT/remote.lock.json" example/src/remote/main.js
+expect "hello remote" --allow-remote --cache-dir "$OUT/cache" --remote-lock "$OUT/remote.lock.json" example/src/remote/main.js
+expect "hello remote" --allow-remote --cache-dir "$OUT/cache" --remote-lock "$OUT/remote.lock.json" --check example/src/remote/main.js
+printf '{\n  "https://esm.example.com/greet/index.js": "ff0aede3c333bae6cf85ba91fcad6ed70aaec3b30d6df99a3a8480d69d69f9ad"\n}' > "$OUT/partial.lock.json"
+expect_error "check: $OUT/partial.lock.json differs from line 2" --allow-remote --cache-dir "$OUT/cache" --remote-lock "$OUT/partial.lock.json" --check example/src/remote/main.js
+expect_no_file "$OUT/unwritten.lock.json" --allow-remote --cache-dir "$OUT/cache" --remote-lock "$OUT/unwritten.lock.json" --write=false example/src/remote/main.js
+expect_error "pass --allow-remote" example/src/remote/main.js
+expect_error "but the lock file pins" --allow-remote --cache-dir "$OUT/cache" --remote-lock example/src/remote/stale.lock.json example/src/remote/main.js
+expect_error "lib@1.0.0 doesn't match the lockfile's 0.9.0" --lockfile example/lockfiles/drifted-lock.json example/src/main.js
+expect "1" --lockfile example/lockfiles/verified-lock.json --package-tarballs example/tarballs example/src/dedupe/main.js
+expect_error "lockfile: vendor_b@1.0.0 has util.js that doesn't match its tarball" --lockfile example/lockfiles/tampered-lock.json --package-tarballs example/tarballs/tampered example/src/dedupe/main.js
+expect_error "lockfile: lib@1.0.0's tarball has integrity sha512-" --lockfile example/lockfiles/tampered-lock.json --package-tarballs example/tarballs/tampered example/src/dedupe/main.js
+expect_error "lockfile: cjs_lib@1.0.0 has no integrity in the lockfile to verify its tarball against" --lockfile example/lockfiles/tampered-lock.json --package-tarballs example/tarballs/tampered example/src/dedupe/main.js
+expect_error "vendor_a@1.0.0 has no tarball at $OUT/vendor_a-1.0.0.tgz" --lockfile example/lockfiles/verified-lock.json --package-tarballs "$OUT" example/src/dedupe/main.js
+expect_file "$OUT/licenses.json" '"LICENSE"' --licenses-out "$OUT/licenses.json" example/src/main.js
+expect_file "$OUT/dual-licenses.json" '"license": "(MIT OR GPL-3.0-or-later)"' --licenses-out "$OUT/dual-licenses.json" example/src/interop/main.js
+expect "bibble wibble" --licenses-out "$OUT/licenses.json" --check example/src/main.js
+expect_error "check: $OUT/licenses.json differs from line" --licenses-out "$OUT/licenses.json" --check example/src/interop/main.js
+expect_error "check: $OUT/missing-licenses.json doesn't exist" --licenses-out "$OUT/missing-licenses.json" --check example/src/main.js
+expect_no_file "$OUT/unwritten-licenses.json" --licenses-out "$OUT/unwritten-licenses.json" --write=false example/src/main.js
+expect "wobble!" --deny-license GPL example/src/interop/main.js
+expect_error "license: vendor_a@1.0.0 is licensed under GPL-3.0-only, which is denied" --deny-license GPL example/src/dedupe/main.js
+expect_file "$OUT/sbom.json" '"purl": "pkg:npm/vendor_b@1.0.0"' --sbom "$OUT/sbom.json" example/src/dedupe/main.js
+expect_file "$OUT/provenance/main.js.provenance.json" '"package": "vendor_b@1.0.0"' --outdir "$OUT/provenance" --provenance example/src/dedupe/main.js
+expect_file "$OUT/provenance/main.min.js.provenance.json" '"file": "example/src/dedupe/main.js"' --outdir "$OUT/provenance" --provenance --minified example/src/dedupe/main.js
+expect_error "policy: vendor_a@1.0.0 is denied by the policy (*): $PWD/example/src/dedupe/main.js -> $PWD/example/third_party/js/vendor_a/index.js" --package-policy example/policies/deny.json example/src/dedupe/main.js
+expect_error "policy: vendor_b@1.0.0 isn't allowed by the policy (>=2 || 0.x)" --package-policy example/policies/allow.json example/src/dedupe/main.js
+expect "1" --package-policy example/policies/ranges.json example/src/dedupe/main.js
+expect "bibble wibble" --package-policy example/policies/deny.json example/src/main.js
+expect_file_without "$OUT/sbom.json" '"name": "lib"' --sbom "$OUT/sbom.json" example/src/dedupe/main.js
+expect "1" --sbom "$OUT/sbom.json" --check example/src/dedupe/main.js
+expect_error "check: $OUT/sbom.json differs from line" --sbom "$OUT/sbom.json" --check example/src/main.js
+expect_error "check: $OUT/missing-sbom.json doesn't exist" --sbom "$OUT/missing-sbom.json" --check example/src/main.js
+expect_no_file "$OUT/unwritten-sbom.json" --sbom "$OUT/unwritten-sbom.json" --write=false example/src/main.js
+expect_error "isn't valid JSON" --virtual 'virtual:greeting=export const greeting = "hi";' --virtual-json 'virtual:build-info={' example/src/virtual/main.js
+expect_error "needs an \"entry\" path" --config example/configs/missing-entry.json
+expect_error "invalid value '0'" --jobs 0 example/src/main.js
+expect_error "'wobble' is not exported by" --dry-run example/src/missing/main.js
+expect_error "'name' is exported by $PWD/example/src/duplicate/first.js and $PWD/example/src/duplicate/second.js" example/src/duplicate/main.js
+expect_stderr "duplicate export: main: 'name' is exported by" --duplicate-exports warn example/src/duplicate/main.js
+expect "local" example/src/duplicate/uses_shadowed.js
+expect "first" example/src/duplicate/uses_picked.js
+expect_error "duplicate/reexport.js:2:10: 'name' is already exported" example/src/duplicate/reexport.js
+expect "cjs" --no-eval example/src/kinds/main.mjs
+expect_error "eval/main.js:1:13: new Function()" --no-eval example/src/eval/main.js
+expect_error "eval/main.js:3:1: setTimeout() with a string" --no-eval example/src/eval/main.js
+expect_error "eval/main.js:4:30: eval" --no-eval example/src/eval/main.js
+expect "client" --client example/src/directives/page.js
+expect_error "use server: form: $PWD/example/src/directives/form.js -> $PWD/example/src/directives/button.js -> $PWD/example/src/directives/actions.js" --client example/src/directives/form.js
+expect "function" example/src/directives/form.js
+expect '1.2.3 "beta" number -40 release a+b https://example.com local shadowed' --const 'VERSION:string=1.2.3 "beta"' --const BUILD_NUMBER:number=-41 --const DEBUG:bool=false --const 'FEATURES:json={"enabled":["a","b"]}' --const process.env.API_URL:string=https://example.com example/src/consts/main.js
+expect_error "BUILD_NUMBER is a number but \"abc\" isn't one" --const BUILD_NUMBER:number=abc example/src/consts/main.js
+expect_error "DEBUG has type \"boolean\", expected string, number, bool or json" --const DEBUG:boolean=true example/src/consts/main.js
+expect '{"VERSION":"1.2.3","DEBUG":false}' --const VERSION:string=1.2.3 --const DEBUG:bool=false example/src/consts/shorthand.js
+expect_error "consts/assign.js:2:5: VERSION is set with --const, so it can't be assigned to" --const VERSION:string=1.2.3 --const DEBUG:bool=true example/src/consts/assign.js
+expect_error "consts/update.js:1:1: BUILD_NUMBER is set with --const, so it can't be assigned to" --const BUILD_NUMBER:number=4 example/src/consts/update.js
+expect "unknown" example/src/pragmas/main.js
+expect "mobile" --flag MOBILE example/src/pragmas/main.js
+expect "desktop modern" --flag DESKTOP example/src/pragmas/main.js
+expect "desktop" --flag DESKTOP --flag LEGACY example/src/pragmas/main.js
+expect_error "pragmas/unclosed.js:1: #if is never closed with an #endif" example/src/pragmas/unclosed.js
+expect_error "namespace isn't supported" example/src/typescript/namespace.ts
+expect_error "enum member Direction.Down needs an initializer" example/src/typescript/uninitialised.ts
+expect_error "circular/a.js -> " --circular error example/src/circular/a.js
+expect_error "missing/main.js:1:18: 'wobble' is not exported by" example/src/missing/main.js
+expect_with "node example/runtime/system.cjs" "bibble wibble" --format system example/src/main.js
+expect_with "node example/runtime/amd.cjs" "bibble wibble" --format amd --external lib --amd-dependency lib=vendor/lib example/src/main.js
+expect_with "node example/runtime/amd.cjs" "bibble" --format amd --external lib --amd-dependency lib=vendor/lib example/src/dynamic/external.js
+expect_with "node example/runtime/amd.cjs" "bibble strict cjs is strict" --format amd --external lib --amd-dependency lib=vendor/lib example/src/strict/main.js
+expect_with "node example/runtime/amd.cjs" "bibble sloppy cjs is strict" --format amd --external lib --amd-dependency lib=vendor/lib --use-strict=false example/src/strict/main.js
+expect_with "node example/runtime/system.cjs" "bibble sloppy cjs is strict" --format system --use-strict=false example/src/strict/main.js
+expect_with "grep -c use.strict" "1" --format amd --external lib --amd-dependency lib=vendor/lib example/src/strict/main.js
+expect_file "$OUT/dynamic-importmap.json" '"lib": "https://cdn.example.com/lib"' --external lib --import-map-out "$OUT/dynamic-importmap.json" --external-url "https://cdn.example.com/[name]" example/src/dynamic/external.js
+expect_file "$OUT/importmap.json" '"lib": "https://cdn.example.com/lib"' --external lib --import-map-out "$OUT/importmap.json" --external-url "https://cdn.example.com/[name]" example/src/main.js
+expect_error "check: $OUT/importmap.json differs from line" --external lib --import-map-out "$OUT/importmap.json" --external-url "https://unpkg.example.com/[name]" --check example/src/main.js
+expect_no_file "$OUT/unwritten-importmap.json" --external lib --import-map-out "$OUT/unwritten-importmap.json" --external-url "https://cdn.example.com/[name]" --write=false example/src/main.js
+expect_outdir "admin/app.js" "wobble!" --input admin/app=example/src/interop/main.js example/src/main.js
+expect_outdir "main.js" "bibble wibble" --input admin/app=example/src/interop/main.js example/src/main.js
+expect_outdir "copy.js" "bibble wibble" --input copy=example/src/main.js --input admin/app=example/src/interop/main.js example/src/main.js
+expect_outdir "interop/main.js" "wobble!" --entry-names "[dir]/[name]" example/src/interop/main.js example/src/local.js
+expect_same_outdirs --sourcemap --minified --input interop=example/src/interop/main.js --input dedupe=example/src/dedupe/main.js example/src/main.js example/src/local.js example/src/circular/a.js
+expect_outdir "main.min.js" "bibble wibble" --minified example/src/main.js
+expect_outdir "main.min.js" "a hello mangle" --minified example/src/mangle/main.js
+expect_file "$OUT/minified/main.min.js.map" '"mappings"' --outdir "$OUT/minified" --minified --sourcemap example/src/main.js
+expect_outdir "mangled.js" "a hello mangle" --config example/configs/entries.json
+expect_file "$OUT/configs/legacy.js" "System.register" --outdir "$OUT/configs" --config example/configs/entries.json
+expect_outdir "main.min.js" "bibble wibble" --verify-output --minified example/src/main.js
+expect "bibble wibble" --sourcemap-validate --map target/validated.js.map example/src/main.js
+expect_with "jq -r .version" "3" --map - -o target/stdout-map.js example/src/main.js
+expect_file target/stdout-map.js "wibble" --map - -o target/stdout-map.js example/src/main.js
+expect_with "node example/runtime/envelope.cjs" "bibble wibble" --envelope example/src/main.js
+expect_file "$OUT/validated/main.min.js.map" '"mappings"' --outdir "$OUT/validated" --minified --sourcemap --sourcemap-validate example/src/main.js
+expect_outdir "main.esm.js" "bibble wibble" --config example/configs/builds.json
+expect_file "$OUT/builds/main.system.js" "System.register" --outdir "$OUT/builds" --config example/configs/builds.json
+expect_file "$OUT/jobs/main.system.js" "System.register" --outdir "$OUT/jobs" --jobs 1 --config example/configs/builds.json
+PLEASE_BUNDLE_JOBS=1 expect_outdir "main.esm.js" "bibble wibble" --config example/configs/builds.json
+expect_error "the builds in example/configs/jobs.json set different --jobs" --outdir "$OUT/jobs" --config example/configs/jobs.json
+expect_outdir "main.esm.js" "bibble wibble" --jobs 1 --config example/configs/jobs.json
+expect_outdir "copy.js" "bibble wibble" --jobs 1 --input copy=example/src/main.js example/src/main.js
+expect_outdir "main.esm.js" "bibble wibble" --format esm --format system --entry-names "[name].[format]" example/src/main.js
+expect_file "$OUT/formats/main.system.js" "System.register" --outdir "$OUT/formats" --format esm --format system --entry-names "[name].[format]" example/src/main.js
+expect_file "$OUT/scripts.html" '<script nomodule>System.import("./main.system.js");</script>' --outdir "$OUT/formats" --format esm --format system --entry-names "[name].[format]" --html-snippet "$OUT/scripts.html" example/src/main.js
+expect_file "$OUT/public/robots.txt" "User-agent: *" --outdir "$OUT/public" --public-dir example/public example/src/main.js
+expect_file "$OUT/public/icons/favicon.svg" "<svg" --outdir "$OUT/public" --public-dir example/public example/src/main.js
+expect_error "main.js in example/src/public would replace the entry written there" --outdir "$OUT/public" --public-dir example/src/public example/src/main.js
+expect_file "$OUT/scripts.html" '<script type="module" src="./main.esm.js"></script>' --outdir "$OUT/formats" --format esm --format system --entry-names "[name].[format]" --html-snippet "$OUT/scripts.html" example/src/main.js
+expect_file "$OUT/check/main.js" "wibble" --outdir "$OUT/check" --sourcemap example/src/main.js
+expect "" --outdir "$OUT/check" --sourcemap --check example/src/main.js
+expect_error "check: $OUT/check/main.js differs from line 1" --outdir "$OUT/check" --sourcemap --check example/src/mangle/main.js
+expect_error "check: $OUT/check/local.js doesn't exist" --outdir "$OUT/check" --check example/src/local.js
+expect_outdir "main.js" "bibble wibble" --compress gzip example/src/main.js
+expect "" --outdir "$OUT" --compress gzip --check example/src/main.js
+expect_round_trip "$OUT/gzip/main.js" "$OUT/gzip/main.js.gz" "gzip -dc" --outdir "$OUT/gzip" --compress gzip example/src/main.js
+if command -v brotli >/dev/null
 then
-	echo "yay"
-	exit 0
+	expect_round_trip "$OUT/brotli/main.js" "$OUT/brotli/main.js.br" "brotli -dc" --outdir "$OUT/brotli" --compress brotli example/src/main.js
 else
-	echo "boo"
-	exit 1
+	expect_no_file "$OUT/brotli/main.js" --outdir "$OUT/brotli" --compress brotli example/src/main.js
 fi
+expect_file "$OUT/lib/main.js" "export { greet as default };" --outdir "$OUT/lib" --lib example/src/lib/main.js
+expect_no_file "$OUT/unwritten/main.js" --outdir "$OUT/unwritten" --write=false example/src/main.js
+expect_error "needs [format]" --outdir "$OUT" --format esm --format system example/src/main.js
+expect_file_without target/reproducible.js.map "$PWD" --reproducible --map target/reproducible.js.map example/src/main.js
+expect_file target/trace.json '"name":"parse '"$PWD/example/src/local.js"'"' --trace target/trace.json example/src/main.js
+expect_file target/failed-trace.json '"cat":"resolve"' --trace target/failed-trace.json example/src/missing/main.js
+expect_stderr "ms $PWD/example/src/local.js (read " --profile-modules example/src/main.js
+expect_stderr "size: 29 example/third_party/js/vendor_b/index.js (vendor_b@1.0.0)" --size-report example/src/dedupe/main.js
+expect_stderr "peer dependency: peer_host@1.0.0 wants vendor_b@^2.0.0 but vendor_b@1.0.0 is bundled" -p example/third_party/js/peer_host -p example/third_party/js/peer_ok example/src/peers/main.js
+expect "host 0 ok 0" -p example/third_party/js/peer_host -p example/third_party/js/peer_ok example/src/peers/main.js
+expect_error "found 1 peer dependency mismatch(es)" --peer-dependencies error -p example/third_party/js/peer_host -p example/third_party/js/peer_ok example/src/peers/main.js
+expect_stderr "peer dependency: peer_of_nightly@1.0.0 wants peer_nightly@^1.0.0 but peer_nightly@nightly is bundled, which can't be checked" -p example/third_party/js/peer_nightly -p example/third_party/js/peer_of_nightly example/src/peers/nightly.js
+expect "built on nightly" -p example/third_party/js/peer_nightly -p example/third_party/js/peer_of_nightly example/src/peers/nightly.js
+expect "bibble wibble" --audit-determinism example/src/main.js
+expect_file target/audit/main.js.map '"version":3' --audit-determinism --outdir target/audit --sourcemap example/src/main.js example/src/local.js
+expect_error "determinism: <stdout> differs from line 1" --audit-determinism --transform upper="node example/runtime/nondeterministic.cjs" example/src/transform/main.js
+
+exit $FAILED